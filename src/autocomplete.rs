@@ -0,0 +1,249 @@
+use crate::catalog::Catalog;
+use crate::sql::{
+    ForeignKey,
+    quote_identifier,
+};
+
+pub const COMPLETION_LIMIT: usize = 50;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Completions {
+    pub tables: Vec<TableCompletion>,
+    pub columns: Vec<ColumnCompletion>,
+    pub joins: Vec<JoinCompletion>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TableCompletion {
+    pub table_name: String,
+    pub table_comment: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ColumnCompletion {
+    pub table_name: String,
+    pub column_name: String,
+    pub column_type: String,
+    pub column_comment: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct JoinCompletion {
+    pub table_name: String,
+    pub constraint_name: String,
+    pub sql: String,
+}
+
+// `prefix` may be qualified as `table.col`, which restricts columns to that table.
+// `context` lists the tables already in the statement; it ranks their columns first
+// and is the starting point for join suggestions.
+pub fn complete(catalog: &Catalog, prefix: &str, context: &[String]) -> Completions {
+    let prefix = prefix.to_lowercase();
+
+    if let Some(i) = prefix.rfind('.') {
+        let (qualifier, column_prefix) = (&prefix[..i], &prefix[i + 1..]);
+        let qualifier = qualifier.trim_matches('`');
+        return Completions {
+            columns: columns(catalog, column_prefix, |t| t.to_lowercase() == qualifier),
+            ..Completions::default()
+        };
+    }
+
+    let mut tables: Vec<TableCompletion> = catalog.tables.iter()
+        .filter(|t| starts_with(&t.table_name, &prefix))
+        .map(|t| TableCompletion {
+            table_name: t.table_name.clone(),
+            table_comment: t.table_comment.clone().unwrap_or_default(),
+        })
+        .collect();
+    tables.truncate(COMPLETION_LIMIT);
+
+    let in_context = |t: &str| context.iter().any(|c| c == t);
+    let mut columns = if context.is_empty() {
+        columns(catalog, &prefix, |_| true)
+    } else {
+        columns(catalog, &prefix, in_context)
+    };
+    columns.truncate(COMPLETION_LIMIT);
+
+    let mut joins: Vec<JoinCompletion> = catalog.foreign_keys.iter()
+        .filter_map(|fk| {
+            let joined = match (in_context(&fk.table_name), in_context(&fk.referenced_table_name)) {
+                (true, false) => &fk.referenced_table_name,
+                (false, true) => &fk.table_name,
+                _ => return None,
+            };
+            if !starts_with(joined, &prefix) {
+                return None;
+            }
+            Some(JoinCompletion {
+                table_name: joined.clone(),
+                constraint_name: fk.constraint_name.clone(),
+                sql: join_sql(fk, joined),
+            })
+        })
+        .collect();
+    joins.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+    joins.truncate(COMPLETION_LIMIT);
+
+    Completions { tables, columns, joins }
+}
+
+fn columns<F>(catalog: &Catalog, prefix: &str, table_filter: F) -> Vec<ColumnCompletion>
+    where F: Fn(&str) -> bool {
+    let mut columns: Vec<ColumnCompletion> = catalog.columns.iter()
+        .filter(|c| table_filter(&c.table_name) && starts_with(&c.column_name, prefix))
+        .map(|c| ColumnCompletion {
+            table_name: c.table_name.clone(),
+            column_name: c.column_name.clone(),
+            column_type: c.column_type.clone(),
+            column_comment: c.column_comment.clone(),
+        })
+        .collect();
+    columns.sort_by(|a, b| a.column_name.cmp(&b.column_name).then_with(|| a.table_name.cmp(&b.table_name)));
+    columns
+}
+
+fn starts_with(name: &str, prefix: &str) -> bool {
+    name.to_lowercase().starts_with(prefix)
+}
+
+fn join_sql(fk: &ForeignKey, joined_table_name: &str) -> String {
+    let conditions: Vec<String> = fk.column_names.iter().zip(fk.referenced_column_names.iter())
+        .map(|(c, r)| format!(
+            "{}.{} = {}.{}",
+            quote_identifier(&fk.referenced_table_name), quote_identifier(r),
+            quote_identifier(&fk.table_name), quote_identifier(c),
+        ))
+        .collect();
+
+    format!("INNER JOIN {} ON {}", quote_identifier(joined_table_name), conditions.join(" AND "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::conditional::Version;
+    use crate::search::SearchIndex;
+    use crate::sql::{
+        SchemaColumn,
+        TableOutlineResult,
+    };
+
+    fn catalog() -> Catalog {
+        let table = |name: &str| TableOutlineResult {
+            table_name: name.to_string(),
+            table_comment: None,
+            table_fqn: format!("shop.{}", name),
+        };
+        let column = |table_name: &str, name: &str| SchemaColumn {
+            table_name: table_name.to_string(),
+            column_name: name.to_string(),
+            data_type: String::from("int"),
+            column_type: String::from("int(11)"),
+            column_key: String::new(),
+            nullable: false,
+            column_default: None,
+            extra: String::new(),
+            character_set_name: None,
+            collation_name: None,
+            generation_expression: None,
+            column_comment: String::new(),
+        };
+
+        Catalog {
+            fingerprint: String::new(),
+            tables: vec![table("Customers"), table("order items"), table("orders")],
+            table_sizes: HashMap::new(),
+            parent_counts: HashMap::new(),
+            child_counts: HashMap::new(),
+            columns: vec![
+                column("Customers", "id"),
+                column("order items", "id"),
+                column("order items", "order_id"),
+                column("orders", "customer_id"),
+                column("orders", "id"),
+            ],
+            foreign_keys: vec![
+                ForeignKey {
+                    constraint_name: String::from("fk_orders_customer"),
+                    table_name: String::from("orders"),
+                    column_names: vec![String::from("customer_id")],
+                    referenced_table_name: String::from("Customers"),
+                    referenced_column_names: vec![String::from("id")],
+                },
+                ForeignKey {
+                    constraint_name: String::from("fk_items_order"),
+                    table_name: String::from("order items"),
+                    column_names: vec![String::from("order_id")],
+                    referenced_table_name: String::from("orders"),
+                    referenced_column_names: vec![String::from("id")],
+                },
+            ],
+            unique_keys: vec![],
+            inferred_relations: vec![],
+            search_index: SearchIndex::default(),
+            loaded_at: String::new(),
+            loaded_at_epoch: 0,
+            version: Version { etag: String::new(), modified_epoch: 0 },
+            }
+    }
+
+    fn columns(completions: &Completions) -> Vec<(&str, &str)> {
+        completions.columns.iter().map(|c| (c.table_name.as_str(), c.column_name.as_str())).collect()
+    }
+
+    #[test]
+    fn prefix_matches_tables_and_columns_case_insensitively() {
+        let completions = complete(&catalog(), "CU", &[]);
+
+        assert_eq!(completions.tables.iter().map(|t| t.table_name.as_str()).collect::<Vec<_>>(), vec!["Customers"]);
+        assert_eq!(columns(&completions), vec![("orders", "customer_id")]);
+        assert!(completions.joins.is_empty());
+    }
+
+    #[test]
+    fn columns_tie_on_name_and_are_ordered_by_table() {
+        let completions = complete(&catalog(), "id", &[]);
+
+        assert_eq!(columns(&completions), vec![("Customers", "id"), ("order items", "id"), ("orders", "id")]);
+    }
+
+    #[test]
+    fn qualified_prefix_completes_columns_of_that_table() {
+        let completions = complete(&catalog(), "`Order Items`.o", &[]);
+
+        assert_eq!(columns(&completions), vec![("order items", "order_id")]);
+        assert!(completions.tables.is_empty());
+    }
+
+    #[test]
+    fn context_restricts_columns_and_suggests_joins_in_both_directions() {
+        let completions = complete(&catalog(), "", &[String::from("orders")]);
+
+        assert_eq!(columns(&completions), vec![("orders", "customer_id"), ("orders", "id")]);
+        assert_eq!(completions.joins, vec![
+            JoinCompletion {
+                table_name: String::from("Customers"),
+                constraint_name: String::from("fk_orders_customer"),
+                sql: String::from("INNER JOIN `Customers` ON `Customers`.`id` = `orders`.`customer_id`"),
+            },
+            JoinCompletion {
+                table_name: String::from("order items"),
+                constraint_name: String::from("fk_items_order"),
+                sql: String::from("INNER JOIN `order items` ON `orders`.`id` = `order items`.`order_id`"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn joins_between_tables_already_in_context_are_not_suggested() {
+        let context = vec![String::from("orders"), String::from("Customers")];
+
+        let completions = complete(&catalog(), "", &context);
+
+        assert_eq!(completions.joins.iter().map(|j| j.table_name.as_str()).collect::<Vec<_>>(), vec!["order items"]);
+    }
+}
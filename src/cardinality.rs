@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+
+use crate::sql::{
+    ForeignKey,
+    SchemaColumn,
+    UniqueKey,
+};
+
+pub const PRIMARY_KEY_NAME: &str = "PRIMARY";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum RelationKind {
+    OneToOne,
+    OneToMany,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Cardinality {
+    pub kind: RelationKind,
+    pub optional: bool,
+    pub label: String,
+}
+
+impl Cardinality {
+    pub fn new(kind: RelationKind, optional: bool) -> Cardinality {
+        let label = format!(
+            "{} ({})",
+            match kind {
+                RelationKind::OneToOne => "1:1",
+                RelationKind::OneToMany => "1:N",
+            },
+            if optional { "optional" } else { "mandatory" },
+        );
+
+        Cardinality {
+            kind,
+            optional,
+            label,
+        }
+    }
+
+    pub fn crows_foot(&self) -> String {
+        let parent = if self.optional { "|o" } else { "||" };
+        let child = match self.kind {
+            RelationKind::OneToOne => "o|",
+            RelationKind::OneToMany => "o{",
+        };
+
+        format!("{}--{}", parent, child)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Junction {
+    pub table_name: String,
+    pub constraint_names: Vec<String>,
+    pub referenced_table_names: Vec<String>,
+}
+
+pub fn of_foreign_key(
+    foreign_key: &ForeignKey,
+    unique_keys: &[UniqueKey],
+    columns: &[SchemaColumn],
+) -> Cardinality {
+    let fk_columns: HashSet<&String> = foreign_key.column_names.iter().collect();
+
+    let unique = unique_keys.iter()
+        .filter(|k| k.table_name == foreign_key.table_name)
+        .any(|k| k.column_names.iter().all(|c| fk_columns.contains(c)));
+
+    let optional = columns.iter()
+        .filter(|c| c.table_name == foreign_key.table_name)
+        .any(|c| c.nullable && fk_columns.contains(&c.column_name));
+
+    Cardinality::new(
+        if unique { RelationKind::OneToOne } else { RelationKind::OneToMany },
+        optional,
+    )
+}
+
+pub fn junction_tables(
+    foreign_keys: &[ForeignKey],
+    unique_keys: &[UniqueKey],
+) -> Vec<Junction> {
+    unique_keys.iter()
+        .filter(|k| k.index_name == PRIMARY_KEY_NAME)
+        .filter_map(|pk| {
+            let pk_columns: HashSet<&String> = pk.column_names.iter().collect();
+
+            let pk_foreign_keys: Vec<&ForeignKey> = foreign_keys.iter()
+                .filter(|fk| fk.table_name == pk.table_name)
+                .filter(|fk| fk.column_names.iter().all(|c| pk_columns.contains(c)))
+                .collect();
+
+            let covered: HashSet<&String> = pk_foreign_keys.iter()
+                .flat_map(|fk| fk.column_names.iter())
+                .collect();
+
+            if pk_foreign_keys.len() == 2 && covered == pk_columns {
+                Some(Junction {
+                    table_name: pk.table_name.clone(),
+                    constraint_names: pk_foreign_keys.iter().map(|fk| fk.constraint_name.clone()).collect(),
+                    referenced_table_names: pk_foreign_keys.iter().map(|fk| fk.referenced_table_name.clone()).collect(),
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fk(name: &str, table: &str, columns: &[&str], parent: &str) -> ForeignKey {
+        ForeignKey {
+            constraint_name: name.to_string(),
+            table_name: table.to_string(),
+            column_names: columns.iter().map(|c| c.to_string()).collect(),
+            referenced_table_name: parent.to_string(),
+            referenced_column_names: columns.iter().map(|_| String::from("id")).collect(),
+        }
+    }
+
+    fn key(table: &str, name: &str, columns: &[&str]) -> UniqueKey {
+        UniqueKey {
+            table_name: table.to_string(),
+            index_name: name.to_string(),
+            column_names: columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn column(table: &str, name: &str, nullable: bool) -> SchemaColumn {
+        SchemaColumn {
+            table_name: table.to_string(),
+            column_name: name.to_string(),
+            data_type: String::from("int"),
+            column_type: String::from("int(11)"),
+            column_key: String::new(),
+            nullable,
+            column_default: None,
+            extra: String::new(),
+            character_set_name: None,
+            collation_name: None,
+            generation_expression: None,
+            column_comment: String::new(),
+        }
+    }
+
+    #[test]
+    fn unique_foreign_key_is_one_to_one() {
+        let fk = fk("fk_profiles_user", "profiles", &["user_id"], "users");
+        let keys = vec![key("profiles", "uq_user", &["user_id"])];
+        let columns = vec![column("profiles", "user_id", false)];
+
+        let cardinality = of_foreign_key(&fk, &keys, &columns);
+
+        assert_eq!(cardinality.kind, RelationKind::OneToOne);
+        assert!(!cardinality.optional);
+        assert_eq!(cardinality.label, "1:1 (mandatory)");
+        assert_eq!(cardinality.crows_foot(), "||--o|");
+    }
+
+    #[test]
+    fn nullable_non_unique_foreign_key_is_optional_one_to_many() {
+        let fk = fk("fk_orders_coupon", "orders", &["coupon_id"], "coupons");
+        // A unique key that only starts with the column does not make it unique.
+        let keys = vec![key("orders", "uq_coupon_customer", &["coupon_id", "customer_id"])];
+        let columns = vec![column("orders", "coupon_id", true), column("coupons", "coupon_id", false)];
+
+        let cardinality = of_foreign_key(&fk, &keys, &columns);
+
+        assert_eq!(cardinality.kind, RelationKind::OneToMany);
+        assert!(cardinality.optional);
+        assert_eq!(cardinality.crows_foot(), "|o--o{");
+    }
+
+    #[test]
+    fn junction_is_a_primary_key_made_of_two_foreign_keys() {
+        let fks = vec![
+            fk("fk_tags_post", "post_tags", &["post_id"], "posts"),
+            fk("fk_tags_tag", "post_tags", &["tag_id"], "tags"),
+            fk("fk_likes_post", "likes", &["post_id"], "posts"),
+            fk("fk_likes_user", "likes", &["user_id"], "users"),
+        ];
+        let keys = vec![
+            key("post_tags", PRIMARY_KEY_NAME, &["post_id", "tag_id"]),
+            key("likes", PRIMARY_KEY_NAME, &["id"]),
+        ];
+
+        let junctions = junction_tables(&fks, &keys);
+
+        assert_eq!(junctions, vec![Junction {
+            table_name: String::from("post_tags"),
+            constraint_names: vec![String::from("fk_tags_post"), String::from("fk_tags_tag")],
+            referenced_table_names: vec![String::from("posts"), String::from("tags")],
+        }]);
+    }
+
+    #[test]
+    fn primary_key_with_extra_columns_is_not_a_junction() {
+        let fks = vec![
+            fk("fk_tags_post", "post_tags", &["post_id"], "posts"),
+            fk("fk_tags_tag", "post_tags", &["tag_id"], "tags"),
+        ];
+        let keys = vec![key("post_tags", PRIMARY_KEY_NAME, &["post_id", "tag_id", "position"])];
+
+        assert!(junction_tables(&fks, &keys).is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::panic::{
+    self,
+    AssertUnwindSafe,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+    Once,
+    RwLock,
+};
+use std::thread;
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use actix_web::http::header::HttpDate;
+use r2d2::PooledConnection;
+use r2d2_mysql::MysqlConnectionManager;
+
+use crate::conditional::Version;
+use crate::error::AppError;
+use crate::search::SearchIndex;
+use crate::inference::{
+    self,
+    InferredRelation,
+};
+use crate::sql::{
+    self,
+    ForeignKey,
+    SchemaColumn,
+    TableOutlineResult,
+    TableSizeResult,
+    UniqueKey,
+};
+
+pub const CATALOG_CHECK_INTERVAL_SECS: &str = "CATALOG_CHECK_INTERVAL_SECS";
+pub const CATALOG_MAX_AGE_SECS: &str = "CATALOG_MAX_AGE_SECS";
+
+pub const CATALOG_CHECK_INTERVAL_SECS_DEFAULT: u64 = 30;
+pub const CATALOG_MAX_AGE_SECS_DEFAULT: u64 = 600;
+
+// Until the first load succeeds, for example while MySQL is still starting.
+const CATALOG_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Catalog {
+    pub fingerprint: String,
+    pub tables: Vec<TableOutlineResult>,
+    pub table_sizes: HashMap<String, TableSizeResult>,
+    pub parent_counts: HashMap<String, u32>,
+    pub child_counts: HashMap<String, u32>,
+    pub columns: Vec<SchemaColumn>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub unique_keys: Vec<UniqueKey>,
+    pub inferred_relations: Vec<InferredRelation>,
+    pub search_index: SearchIndex,
+    pub loaded_at: String,
+    pub loaded_at_epoch: u64,
+    pub version: Version,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CatalogStatus {
+    pub fingerprint: String,
+    pub loaded_at: String,
+    pub table_count: usize,
+    pub column_count: usize,
+    pub foreign_key_count: usize,
+}
+
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries: Vec<(&String, &V)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+impl Catalog {
+    pub fn load(
+        conn: &mut PooledConnection<MysqlConnectionManager>,
+        db_name: &str,
+        previous: Option<&Catalog>,
+    ) -> mysql::Result<Catalog> {
+        let now = SystemTime::now();
+        let mut catalog = Catalog {
+            fingerprint: sql::query_catalog_fingerprint(conn, db_name)?,
+            tables: sql::query_table_outline(conn, String::from(db_name))?,
+            table_sizes: sql::query_table_size_map(conn, String::from(db_name))?,
+            parent_counts: sql::query_table_referencing_count_to_parent_map(conn, String::from(db_name))?,
+            child_counts: sql::query_table_referenced_count_from_children_map(conn, String::from(db_name))?,
+            columns: sql::query_schema_columns(conn, String::from(db_name))?,
+            foreign_keys: sql::query_foreign_keys(conn, String::from(db_name))?,
+            unique_keys: sql::query_unique_keys(conn, String::from(db_name))?,
+            inferred_relations: vec![],
+            search_index: SearchIndex::default(),
+            loaded_at: HttpDate::from(now).to_string(),
+            loaded_at_epoch: now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            version: Version { etag: String::new(), modified_epoch: 0 },
+        };
+        catalog.derive(previous);
+
+        catalog.version = Version::of(
+            &catalog.listing_content(), previous.map(|p| &p.version), catalog.loaded_at_epoch);
+        Ok(catalog)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn listing_content(&self) -> (
+        &[TableOutlineResult],
+        Vec<(&String, &TableSizeResult)>,
+        Vec<(&String, &u32)>,
+        Vec<(&String, &u32)>,
+        &[SchemaColumn],
+        &[ForeignKey],
+        &[UniqueKey],
+    ) {
+        (
+            &self.tables,
+            sorted(&self.table_sizes),
+            sorted(&self.parent_counts),
+            sorted(&self.child_counts),
+            &self.columns,
+            &self.foreign_keys,
+            &self.unique_keys,
+        )
+    }
+
+    // Fills in what is computed from the loaded rows, so pages don't redo it per request.
+    // The search index is kept from `previous` when the schema has not changed, as on a
+    // reload for max age.
+    pub fn derive(&mut self, previous: Option<&Catalog>) {
+        self.inferred_relations = inference::infer_relations(&self.columns, &self.foreign_keys);
+        self.search_index = match previous {
+            Some(p) if p.fingerprint == self.fingerprint => p.search_index.clone(),
+            _ => SearchIndex::build(&self.tables, &self.columns),
+        };
+    }
+
+    pub fn table_names(&self) -> Vec<String> {
+        self.tables.iter().map(|t| t.table_name.clone()).collect()
+    }
+
+    pub fn status(&self) -> CatalogStatus {
+        CatalogStatus {
+            fingerprint: self.fingerprint.clone(),
+            loaded_at: self.loaded_at.clone(),
+            table_count: self.tables.len(),
+            column_count: self.columns.len(),
+            foreign_key_count: self.foreign_keys.len(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CatalogCache {
+    catalog: Arc<RwLock<Option<Arc<Catalog>>>>,
+    refresher: Arc<Once>,
+    loading: Arc<Mutex<()>>,
+    check_interval: Duration,
+    max_age: Duration,
+}
+
+impl CatalogCache {
+    pub fn new(check_interval_secs: u64, max_age_secs: u64) -> CatalogCache {
+        CatalogCache {
+            catalog: Arc::new(RwLock::new(None)),
+            refresher: Arc::new(Once::new()),
+            loading: Arc::new(Mutex::new(())),
+            check_interval: Duration::from_secs(check_interval_secs),
+            max_age: Duration::from_secs(max_age_secs),
+        }
+    }
+
+    // Returns the cached catalog, loading it when the background thread has not yet.
+    pub fn get(
+        &self,
+        pool: &Arc<r2d2::Pool<MysqlConnectionManager>>,
+        db_name: &str,
+    ) -> Result<Arc<Catalog>, AppError> {
+        self.start(pool, db_name);
+
+        let cached = self.catalog.read().unwrap().clone();
+        match cached {
+            Some(catalog) => Ok(catalog),
+            None => self.load_if_empty(pool, db_name),
+        }
+    }
+
+    // On a cold start every waiting request would otherwise run the full catalog load
+    // itself; the first one loads while the others wait for its result.
+    fn load_if_empty(
+        &self,
+        pool: &r2d2::Pool<MysqlConnectionManager>,
+        db_name: &str,
+    ) -> Result<Arc<Catalog>, AppError> {
+        let _loading = self.loading.lock().unwrap_or_else(|e| e.into_inner());
+        let cached = self.catalog.read().unwrap().clone();
+        match cached {
+            Some(catalog) => Ok(catalog),
+            None => self.refresh(pool, db_name),
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.catalog.read().unwrap().is_some()
+    }
+
+    // Starts the background thread that loads the catalog and keeps it fresh. Only the
+    // first call has an effect.
+    pub fn start(
+        &self,
+        pool: &Arc<r2d2::Pool<MysqlConnectionManager>>,
+        db_name: &str,
+    ) {
+        self.refresher.call_once(|| self.start_refresher(pool.clone(), String::from(db_name)));
+    }
+
+    pub fn refresh(
+        &self,
+        pool: &r2d2::Pool<MysqlConnectionManager>,
+        db_name: &str,
+    ) -> Result<Arc<Catalog>, AppError> {
+        let previous = self.catalog.read().unwrap().clone();
+        let catalog = Arc::new(Catalog::load(&mut pool.get()?, db_name, previous.as_deref())?);
+        *self.catalog.write().unwrap() = Some(catalog.clone());
+        Ok(catalog)
+    }
+
+    // Every check interval the cheap fingerprint query runs; the full catalog is
+    // reloaded when the fingerprint differs or the catalog is older than max age,
+    // which also picks up changed size statistics.
+    fn start_refresher(
+        &self,
+        pool: Arc<r2d2::Pool<MysqlConnectionManager>>,
+        db_name: String,
+    ) {
+        let cache = self.clone();
+
+        thread::spawn(move || loop {
+            // A failed query must not stop the refresher; the next check retries.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| cache.check(&pool, &db_name)));
+            thread::sleep(if cache.is_loaded() { cache.check_interval } else { CATALOG_RETRY_INTERVAL });
+        });
+    }
+
+    fn check(
+        &self,
+        pool: &r2d2::Pool<MysqlConnectionManager>,
+        db_name: &str,
+    ) {
+        let current = match self.catalog.read().unwrap().clone() {
+            Some(c) => c,
+            None => {
+                let _ = self.load_if_empty(pool, db_name);
+                return;
+            }
+        };
+        let mut conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(_) => return,
+        };
+
+        let age = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs().saturating_sub(current.loaded_at_epoch))
+            .unwrap_or(0);
+        let changed = match sql::query_catalog_fingerprint(&mut conn, db_name) {
+            Ok(fingerprint) => fingerprint != current.fingerprint,
+            Err(_) => return,
+        };
+        if age >= self.max_age.as_secs() || changed {
+            if let Ok(catalog) = Catalog::load(&mut conn, db_name, Some(&current)) {
+                *self.catalog.write().unwrap() = Some(Arc::new(catalog));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(fingerprint: &str) -> Catalog {
+        Catalog {
+            fingerprint: String::from(fingerprint),
+            tables: vec![TableOutlineResult {
+                table_name: String::from("orders"),
+                table_comment: None,
+                table_fqn: String::from("shop.orders"),
+            }],
+            table_sizes: HashMap::new(),
+            parent_counts: HashMap::new(),
+            child_counts: HashMap::new(),
+            columns: vec![],
+            foreign_keys: vec![],
+            unique_keys: vec![],
+            inferred_relations: vec![],
+            search_index: SearchIndex::default(),
+            loaded_at: String::new(),
+            loaded_at_epoch: 0,
+            version: Version { etag: String::new(), modified_epoch: 0 },
+        }
+    }
+
+    #[test]
+    fn search_index_is_built_once_per_schema() {
+        let mut first = catalog("a");
+        first.derive(None);
+        assert_eq!(first.search_index.documents.len(), 1);
+
+        // An emptied index shows whether the previous one was reused.
+        let mut previous = first.clone();
+        previous.search_index.documents.clear();
+
+        let mut same = catalog("a");
+        same.derive(Some(&previous));
+        assert!(same.search_index.documents.is_empty());
+
+        let mut changed = catalog("b");
+        changed.derive(Some(&previous));
+        assert_eq!(changed.search_index, first.search_index);
+    }
+}
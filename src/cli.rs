@@ -0,0 +1,220 @@
+use std::fs;
+use std::io::{
+    self,
+    Write,
+};
+use std::path::Path;
+
+use clap::{
+    App,
+    AppSettings,
+    Arg,
+    ArgMatches,
+    SubCommand,
+};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    ddl,
+    diagram,
+    diff,
+    lint,
+    handler,
+    settings,
+    site,
+    sql,
+};
+use crate::catalog::Catalog;
+use crate::datasource::DataSource;
+use crate::diff::{
+    Change,
+    ChangeKind,
+};
+use crate::error::AppError;
+use crate::lint::Finding;
+use crate::settings::Settings;
+use crate::snapshot::Snapshot;
+
+pub const EXIT_OK: i32 = 0;
+// `diff` found differences or `lint` found problems; the command itself worked.
+pub const EXIT_FOUND: i32 = 1;
+pub const EXIT_USAGE: i32 = 64;
+
+const EXIT_STATUS_HELP: &str = "EXIT STATUS:
+    0     Success, and diff or lint found nothing
+    1     diff found differences, or lint found problems
+    64    Invalid command line
+    65    A snapshot file is invalid
+    69    The database is unreachable
+    70    A query or template failed
+    74    A file could not be read or written
+    75    A query timed out
+    78    Invalid configuration";
+
+pub fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Browses the schema of a MySQL database")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .after_help(EXIT_STATUS_HELP)
+        .subcommand(SubCommand::with_name("serve")
+            .about("Serves the schema browser over HTTP")
+            .args(&settings::args())
+            .args(&settings::server_args()))
+        .subcommand(SubCommand::with_name("snapshot")
+            .about("Writes the schema as JSON, for comparing later with diff")
+            .args(&settings::args())
+            .arg(output_arg()))
+        .subcommand(SubCommand::with_name("export")
+            .about("Writes the schema as a DDL script, a Mermaid ER diagram or a static HTML site")
+            .args(&settings::args())
+            .arg(format_arg(&["sql", "mermaid", "site"]).required(true))
+            .arg(output_arg().required_if("format", "site")
+                .help("Write to this file instead of standard output; for site, the directory to write")))
+        .subcommand(SubCommand::with_name("diff")
+            .about("Compares two snapshots")
+            .arg(Arg::with_name("before").required(true).value_name("BEFORE")
+                .help("Snapshot file, or - for standard input"))
+            .arg(Arg::with_name("after").required(true).value_name("AFTER")
+                .help("Snapshot file, or - for standard input"))
+            .arg(format_arg(&["text", "json"]).default_value("text")))
+        .subcommand(SubCommand::with_name("lint")
+            .about("Finds unindexed foreign keys and duplicate or redundant indexes")
+            .args(&settings::args())
+            .arg(Arg::with_name("snapshot").long("snapshot").value_name("FILE")
+                .help("Check a snapshot file instead of the database"))
+            .arg(format_arg(&["text", "json"]).default_value("text")))
+}
+
+fn format_arg<'a, 'b>(formats: &'a [&'a str]) -> Arg<'a, 'b> {
+    Arg::with_name("format").long("format").short("f").value_name("FORMAT")
+        .possible_values(formats)
+        .help("Output format")
+}
+
+fn output_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("output").long("output").short("o").value_name("FILE")
+        .help("Write to this file instead of standard output")
+}
+
+// Runs every command but `serve` and returns the exit status.
+pub fn run(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<i32, AppError> {
+    match matches.subcommand() {
+        ("snapshot", Some(m)) => snapshot(m, env),
+        ("export", Some(m)) => export(m, env),
+        ("diff", Some(m)) => compare(m),
+        ("lint", Some(m)) => check(m, env),
+        _ => Ok(EXIT_USAGE),
+    }
+}
+
+fn connect(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<DataSource, AppError> {
+    let settings = Settings::from_matches(matches, env)?;
+    DataSource::new(&settings.datasource)
+}
+
+fn snapshot(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<i32, AppError> {
+    let datasource = connect(matches, env)?;
+    let mut conn = datasource.conn_pool.get()?;
+    let snapshot = Snapshot::take(&mut conn, &datasource.name)?;
+
+    write_output(matches.value_of("output"), &json(&snapshot)?)?;
+    Ok(EXIT_OK)
+}
+
+fn export(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<i32, AppError> {
+    if matches.value_of("format") == Some("site") {
+        return export_site(matches, env);
+    }
+    let datasource = connect(matches, env)?;
+    let mut conn = datasource.conn_pool.get()?;
+    let db_name = &datasource.name;
+
+    let text = match matches.value_of("format") {
+        Some("mermaid") => {
+            let catalog = Catalog::load(&mut conn, db_name, None)?;
+            diagram::mermaid_er(&catalog.table_names(), &catalog.foreign_keys, &catalog.unique_keys, &catalog.columns)
+        },
+        _ => ddl::load_schema_script(&mut conn, db_name)?,
+    };
+
+    write_output(matches.value_of("output"), &text)?;
+    Ok(EXIT_OK)
+}
+
+fn export_site(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<i32, AppError> {
+    let settings = Settings::from_matches(matches, env)?;
+    let datasource = DataSource::new(&settings.datasource)?;
+    let tera = handler::templates()?;
+    let dir = matches.value_of("output").expect("--output is required for --format site");
+
+    site::export(&datasource, &tera, settings.features, Path::new(dir))?;
+    Ok(EXIT_OK)
+}
+
+fn compare(matches: &ArgMatches) -> Result<i32, AppError> {
+    let (before, after) = (matches.value_of("before").unwrap_or("-"), matches.value_of("after").unwrap_or("-"));
+    if before == "-" && after == "-" {
+        return Err(AppError::Input(String::from("only one snapshot can be read from standard input")));
+    }
+    let changes = diff::diff(&Snapshot::read(before)?, &Snapshot::read(after)?);
+
+    let text = match matches.value_of("format") {
+        Some("json") => json(&changes)?,
+        _ => changes.iter().map(change_line).join(""),
+    };
+    write_output(None, &text)?;
+    Ok(if changes.is_empty() { EXIT_OK } else { EXIT_FOUND })
+}
+
+fn change_line(change: &Change) -> String {
+    let (sign, description) = match change.change {
+        ChangeKind::Added => ("+", change.after.clone().unwrap_or_default()),
+        ChangeKind::Removed => ("-", change.before.clone().unwrap_or_default()),
+        ChangeKind::Changed => ("~", format!(
+            "{} -> {}", change.before.as_deref().unwrap_or(""), change.after.as_deref().unwrap_or(""))),
+    };
+    format!("{} {} {}: {}\n", sign, change.object.label(), change.name, description)
+}
+
+fn check(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<i32, AppError> {
+    let findings: Vec<Finding> = match matches.value_of("snapshot") {
+        Some(path) => {
+            let snapshot = Snapshot::read(path)?;
+            lint::lint(&snapshot.foreign_keys, &snapshot.indexes)
+        },
+        None => {
+            let datasource = connect(matches, env)?;
+            let mut conn = datasource.conn_pool.get()?;
+            let foreign_keys = sql::query_foreign_keys(&mut conn, datasource.name.clone())?;
+            let indexes = sql::query_indexes(&mut conn, &datasource.name, None)?;
+            lint::lint(&foreign_keys, &indexes)
+        },
+    };
+
+    let text = match matches.value_of("format") {
+        Some("json") => json(&findings)?,
+        _ => findings.iter()
+            .map(|f| format!("{}: {}\n    {}\n", f.table_name, f.message, f.ddl))
+            .join(""),
+    };
+    write_output(None, &text)?;
+    Ok(if findings.is_empty() { EXIT_OK } else { EXIT_FOUND })
+}
+
+fn json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string_pretty(value)
+        .map(|s| s + "\n")
+        .map_err(|e| AppError::Io(format!("could not write JSON: {}", e)))
+}
+
+fn write_output(path: Option<&str>, text: &str) -> Result<(), AppError> {
+    match path {
+        Some(p) => fs::write(p, text)
+            .map_err(|e| AppError::Io(format!("could not write {}: {}", p, e))),
+        None => io::stdout().write_all(text.as_bytes())
+            .map_err(|e| AppError::Io(format!("could not write to standard output: {}", e))),
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::hash::{
+    Hash,
+    Hasher,
+};
+use std::sync::{
+    Arc,
+    RwLock,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use actix_web::{
+    dev::HttpResponseBuilder,
+    http::header::{
+        self,
+        HttpDate,
+    },
+    HttpRequest,
+    HttpResponse,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Version {
+    pub etag: String,
+    pub modified_epoch: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Validator {
+    pub etag: String,
+    pub last_modified: SystemTime,
+}
+
+// Versions of pages rendered from live queries as well as the catalog, such as the table
+// page with its triggers, partitions and the views reading it. They are versioned by the
+// rendered content, so any input that changes the page changes the ETag.
+#[derive(Clone, Default)]
+pub struct PageVersions {
+    versions: Arc<RwLock<HashMap<String, Version>>>,
+}
+
+// FNV-1a, so the same content hashes to the same ETag in every process.
+pub struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Fnv64 {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+pub fn etag<T: Hash + ?Sized>(value: &T) -> String {
+    let mut hasher = Fnv64::default();
+    value.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+impl Version {
+    // Keeps the previous modification time while the content hash is unchanged.
+    pub fn of<T: Hash + ?Sized>(value: &T, previous: Option<&Version>, now_epoch: u64) -> Version {
+        let etag = etag(value);
+        let modified_epoch = match previous {
+            Some(p) if p.etag == etag => p.modified_epoch,
+            _ => now_epoch,
+        };
+        Version { etag, modified_epoch }
+    }
+}
+
+impl PageVersions {
+    pub fn new() -> PageVersions {
+        PageVersions::default()
+    }
+
+    // Last-Modified is when this content of the page was first rendered.
+    pub fn validator<T: Hash + ?Sized>(&self, page: &str, content: &T, now_epoch: u64) -> Validator {
+        let mut versions = self.versions.write().unwrap();
+        let version = Version::of(content, versions.get(page), now_epoch);
+        versions.insert(String::from(page), version.clone());
+        Validator::new(&version)
+    }
+}
+
+impl Validator {
+    pub fn new(version: &Version) -> Validator {
+        Validator {
+            etag: version.etag.clone(),
+            last_modified: UNIX_EPOCH + Duration::from_secs(version.modified_epoch),
+        }
+    }
+
+    // Mixes state that is rendered alongside the catalog, such as exact row counts,
+    // into the validator.
+    pub fn with<T: Hash + ?Sized>(self, value: &T, modified_epoch: u64) -> Validator {
+        let modified = UNIX_EPOCH + Duration::from_secs(modified_epoch);
+        Validator {
+            etag: etag(&(&self.etag, value)),
+            last_modified: self.last_modified.max(modified),
+        }
+    }
+
+    pub fn is_fresh(&self, req: &HttpRequest) -> bool {
+        if let Some(value) = req.headers().get(header::IF_NONE_MATCH) {
+            return value.to_str()
+                .map(|v| v.split(',').map(|t| t.trim()).any(|t| t == "*" || weak_eq(t, &self.etag)))
+                .unwrap_or(false);
+        }
+
+        req.headers().get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<HttpDate>().ok())
+            .map(|since| self.last_modified <= SystemTime::from(since))
+            .unwrap_or(false)
+    }
+
+    pub fn not_modified(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        if self.is_fresh(req) {
+            Some(self.headers(&mut HttpResponse::NotModified()).finish())
+        } else {
+            None
+        }
+    }
+
+    pub fn headers<'a>(&self, builder: &'a mut HttpResponseBuilder) -> &'a mut HttpResponseBuilder {
+        builder
+            .header(header::ETAG, self.etag.as_str())
+            .header(header::LAST_MODIFIED, HttpDate::from(self.last_modified))
+            .header(header::CACHE_CONTROL, "no-cache")
+    }
+}
+
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
@@ -2,79 +2,251 @@ extern crate mysql;
 extern crate r2d2;
 extern crate r2d2_mysql;
 
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    Mutex,
+    atomic::{
+        AtomicU64,
+        Ordering,
+    },
+};
+use std::time::Duration;
 
 use mysql::{
+    Conn,
     Opts,
     OptsBuilder,
 };
+use r2d2::event::{
+    AcquireEvent,
+    CheckoutEvent,
+    HandleEvent,
+    ReleaseEvent,
+    TimeoutEvent,
+};
 use r2d2_mysql::MysqlConnectionManager;
 
+use crate::error::AppError;
+use crate::settings::{
+    DataSourceSettings,
+    TlsSettings,
+};
+use crate::watchdog::{
+    self,
+    ConnectionIds,
+    SessionTimeout,
+};
+
+pub const DATABASE_URL: &str = "DATABASE_URL";
 pub const DATABASE_HOST: &str = "DATABASE_HOST";
 pub const DATABASE_PORT: &str = "DATABASE_PORT";
+pub const DATABASE_SOCKET: &str = "DATABASE_SOCKET";
 pub const DATABASE_USERNAME: &str = "DATABASE_USERNAME";
 pub const DATABASE_PASSWORD: &str = "DATABASE_PASSWORD";
+pub const DATABASE_PASSWORD_FILE: &str = "DATABASE_PASSWORD_FILE";
 pub const DATABASE_NAME: &str = "DATABASE_NAME";
 pub const DATABASE_POOL_SIZE: &str = "DATABASE_POOL_SIZE";
+pub const DATABASE_TLS_CA: &str = "DATABASE_TLS_CA";
+pub const DATABASE_TLS_CERT: &str = "DATABASE_TLS_CERT";
+pub const DATABASE_TLS_KEY: &str = "DATABASE_TLS_KEY";
+pub const DATABASE_TLS_VERIFY_PEER: &str = "DATABASE_TLS_VERIFY_PEER";
 
 pub const DATABASE_HOST_DEFAULT: &str = "127.0.0.1";
-pub const DATABASE_PORT_DEFAULT: u32 = 3306;
+pub const DATABASE_PORT_DEFAULT: u16 = 3306;
 pub const DATABASE_POOL_SIZE_DEFAULT: u32 = 4;
 
+// Checkouts slower than this count as waits. r2d2 pings every connection it hands out,
+// so a checkout's duration includes one round trip to the server even when a connection
+// was idle; the threshold sits well above that, and a checkout slower than it most likely
+// waited for a connection to be returned or opened.
+pub const POOL_WAIT_THRESHOLD: Duration = Duration::from_millis(100);
+// How long a request waits for a connection before it gets the "database unavailable" page.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct DataSource {
     pub host: String,
-    pub port: u32,
+    pub port: u16,
     pub username: String,
     pub name: String,
     pub pool_size: u32,
+    pub query_timeout_secs: u64,
     pub conn_pool: Arc<r2d2::Pool<MysqlConnectionManager>>,
+    pub pool_events: Arc<PoolEvents>,
+    opts: Opts,
+    // Readiness checks use a connection of their own, so a pool whose connections are all
+    // busy under load does not make a healthy instance look unreachable.
+    probe: Mutex<Option<Conn>>,
+}
+
+#[derive(Debug, Default)]
+pub struct PoolEvents {
+    checkouts: AtomicU64,
+    waits: AtomicU64,
+    wait_ms_total: AtomicU64,
+    wait_ms_max: AtomicU64,
+    timeouts: AtomicU64,
+    opened: AtomicU64,
+    closed: AtomicU64,
+}
+
+// `waits` and the wait times only count checkouts slower than `wait_threshold_ms`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PoolStats {
+    pub max_size: u32,
+    pub connections: u32,
+    pub in_use: u32,
+    pub idle: u32,
+    pub checkouts: u64,
+    pub wait_threshold_ms: u64,
+    pub waits: u64,
+    pub wait_ms_total: u64,
+    pub wait_ms_max: u64,
+    pub timeouts: u64,
+    pub connections_opened: u64,
+    pub connections_closed: u64,
+}
+
+#[derive(Debug)]
+struct PoolEventHandler(Arc<PoolEvents>);
+
+impl HandleEvent for PoolEventHandler {
+    fn handle_acquire(&self, _event: AcquireEvent) {
+        self.0.opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn handle_release(&self, _event: ReleaseEvent) {
+        self.0.closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        self.0.checkouts.fetch_add(1, Ordering::Relaxed);
+        if event.duration() >= POOL_WAIT_THRESHOLD {
+            let ms = event.duration().as_millis() as u64;
+            self.0.waits.fetch_add(1, Ordering::Relaxed);
+            self.0.wait_ms_total.fetch_add(ms, Ordering::Relaxed);
+            self.0.wait_ms_max.fetch_max(ms, Ordering::Relaxed);
+        }
+    }
+
+    fn handle_timeout(&self, _event: TimeoutEvent) {
+        self.0.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl DataSource {
-    pub fn new(
-        host: String,
-        port: u32,
-        username: String,
-        password: String,
-        name: String,
-        pool_size: u32,
-    ) -> DataSource {
-        assert_ne!(host, "");
-        assert!(port > 0);
-        assert_ne!(username, "");
-        assert_ne!(password, "");
-        assert_ne!(name, "");
-        assert!(pool_size > 0);
-
-        let conn_url = format!(
-            "mysql://{user}:{pass}@{host}:{port}/{name}",
-            user = username,
-            pass = password,
-            host = host,
-            port = port,
-            name = name,
-        );
-
-        let conn_pool = conn_pool(&conn_url, pool_size);
-
-        DataSource {
-            host,
-            port,
-            username,
-            name,
-            pool_size,
+    pub fn new(settings: &DataSourceSettings) -> Result<DataSource, AppError> {
+        let opts = opts(settings)?;
+        let pool_events = Arc::new(PoolEvents::default());
+        let connection_ids = ConnectionIds::default();
+        let session_timeout = SessionTimeout {
+            connection_ids: connection_ids.clone(),
+            timeout_secs: settings.query_timeout_secs,
+        };
+        let conn_pool = conn_pool(opts.clone(), settings.pool_size, pool_events.clone(), session_timeout);
+        watchdog::start(opts.clone(), connection_ids, settings.query_timeout_secs);
+
+        Ok(DataSource {
+            host: settings.host.clone(),
+            port: settings.port,
+            username: settings.username.clone(),
+            name: settings.name.clone(),
+            pool_size: settings.pool_size,
+            query_timeout_secs: settings.query_timeout_secs,
             conn_pool,
+            pool_events,
+            opts,
+            probe: Mutex::new(None),
+        })
+    }
+
+    // True when the server answers a ping, or a new connection opens, within the timeout.
+    pub fn is_reachable(&self, timeout: Duration) -> bool {
+        let mut probe = self.probe.lock().unwrap();
+        if probe.as_mut().is_some_and(|conn| conn.ping()) {
+            return true;
         }
+
+        let mut builder = OptsBuilder::from_opts(self.opts.clone());
+        builder
+            .tcp_connect_timeout(Some(timeout))
+            .read_timeout(Some(timeout))
+            .write_timeout(Some(timeout));
+        *probe = Conn::new(builder).ok();
+        probe.is_some()
     }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        let state = self.conn_pool.state();
+        let events = &self.pool_events;
+        PoolStats {
+            max_size: self.conn_pool.max_size(),
+            connections: state.connections,
+            in_use: state.connections - state.idle_connections,
+            idle: state.idle_connections,
+            checkouts: events.checkouts.load(Ordering::Relaxed),
+            wait_threshold_ms: POOL_WAIT_THRESHOLD.as_millis() as u64,
+            waits: events.waits.load(Ordering::Relaxed),
+            wait_ms_total: events.wait_ms_total.load(Ordering::Relaxed),
+            wait_ms_max: events.wait_ms_max.load(Ordering::Relaxed),
+            timeouts: events.timeouts.load(Ordering::Relaxed),
+            connections_opened: events.opened.load(Ordering::Relaxed),
+            connections_closed: events.closed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Built from discrete options, so a password containing `@` or `/` needs no escaping.
+pub fn opts(settings: &DataSourceSettings) -> Result<Opts, AppError> {
+    let mut builder = match settings.url {
+        Some(ref url) => OptsBuilder::from_opts(Opts::from_url(url)
+            .map_err(|e| AppError::Config(format!("datasource.url is invalid: {}", e)))?),
+        None => OptsBuilder::new(),
+    };
+    builder
+        .ip_or_hostname(Some(settings.host.as_str()))
+        .tcp_port(settings.port)
+        .socket(settings.socket.as_ref())
+        .user(Some(settings.username.as_str()))
+        .pass(Some(settings.password.as_str()).filter(|p| !p.is_empty()))
+        .db_name(Some(settings.name.as_str()));
+    if let Some(ref tls) = settings.tls {
+        tls_opts(&mut builder, tls)?;
+    }
+    Ok(Opts::from(builder))
+}
+
+#[cfg(all(feature = "tls", unix, not(target_os = "macos")))]
+fn tls_opts(builder: &mut OptsBuilder, tls: &TlsSettings) -> Result<(), AppError> {
+    let client = tls.client.as_ref().map(|c| (c.cert.clone(), c.key.clone()));
+    builder
+        .ssl_opts(Some((tls.ca.clone(), client)))
+        .verify_peer(tls.verify_peer);
+    Ok(())
+}
+
+// Without the feature the mysql crate drops ssl_opts silently, which would connect in plain text.
+#[cfg(not(all(feature = "tls", unix, not(target_os = "macos"))))]
+fn tls_opts(_: &mut OptsBuilder, _: &TlsSettings) -> Result<(), AppError> {
+    Err(AppError::Config(String::from(
+        "datasource.tls is set but this build has no TLS support; rebuild with `--features tls`",
+    )))
 }
 
-fn conn_pool(conn_url: &String, pool_size: u32) -> Arc<r2d2::Pool<MysqlConnectionManager>> {
-    let opts = Opts::from_url(&conn_url).unwrap();
+fn conn_pool(
+    opts: Opts,
+    pool_size: u32,
+    pool_events: Arc<PoolEvents>,
+    session_timeout: SessionTimeout,
+) -> Arc<r2d2::Pool<MysqlConnectionManager>> {
     let builder = OptsBuilder::from_opts(opts);
     let manager = MysqlConnectionManager::new(builder);
 
+    // Built unchecked so the server starts while MySQL is down; r2d2 keeps opening
+    // connections in the background with backoff until it comes up.
     Arc::new(r2d2::Pool::builder()
         .max_size(pool_size)
-        .build(manager)
-        .unwrap())
+        .event_handler(Box::new(PoolEventHandler(pool_events)))
+        .connection_customizer(Box::new(session_timeout))
+        .connection_timeout(CONNECTION_TIMEOUT)
+        .build_unchecked(manager))
 }
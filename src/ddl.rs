@@ -0,0 +1,177 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use r2d2::PooledConnection;
+use r2d2_mysql::MysqlConnectionManager;
+
+use crate::sql::{
+    self,
+    ForeignKey,
+};
+use crate::view::{
+    self,
+    ViewDependencies,
+};
+
+pub fn table_order(table_names: &[String], foreign_keys: &[ForeignKey]) -> Vec<String> {
+    let depends_on: BTreeMap<String, BTreeSet<String>> = table_names.iter()
+        .map(|t| (t.clone(), foreign_keys.iter()
+            .filter(|fk| &fk.table_name == t && &fk.referenced_table_name != t)
+            .map(|fk| fk.referenced_table_name.clone())
+            .collect()))
+        .collect();
+
+    dependency_order(depends_on)
+}
+
+pub fn view_order(views: &[(String, ViewDependencies)]) -> Vec<String> {
+    let view_names: BTreeSet<&String> = views.iter().map(|(name, _)| name).collect();
+
+    let depends_on: BTreeMap<String, BTreeSet<String>> = views.iter()
+        .map(|(name, deps)| (name.clone(), deps.table_names.iter()
+            .filter(|t| view_names.contains(t) && *t != name)
+            .cloned()
+            .collect()))
+        .collect();
+
+    dependency_order(depends_on)
+}
+
+// Emits every name after the names it depends on. Names caught in a cycle are emitted
+// in alphabetical order once nothing else can be resolved.
+fn dependency_order(mut depends_on: BTreeMap<String, BTreeSet<String>>) -> Vec<String> {
+    let known: BTreeSet<String> = depends_on.keys().cloned().collect();
+    for deps in depends_on.values_mut() {
+        deps.retain(|d| known.contains(d));
+    }
+
+    let mut ordered: Vec<String> = vec![];
+    while !depends_on.is_empty() {
+        let ready: Vec<String> = depends_on.iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let emitted = if ready.is_empty() {
+            vec![depends_on.keys().next().cloned().unwrap()]
+        } else {
+            ready
+        };
+
+        for name in emitted {
+            depends_on.remove(&name);
+            for deps in depends_on.values_mut() {
+                deps.remove(&name);
+            }
+            ordered.push(name);
+        }
+    }
+
+    ordered
+}
+
+pub fn schema_script(db_name: &str, statements: &[(String, String)]) -> String {
+    let mut script = format!("-- Schema `{}`\n\nSET FOREIGN_KEY_CHECKS = 0;\n\n", db_name);
+
+    for (name, ddl) in statements {
+        script.push_str(&format!("-- {}\n{};\n\n", name, ddl));
+    }
+
+    script.push_str("SET FOREIGN_KEY_CHECKS = 1;\n");
+    script
+}
+
+// CREATE statements for every table and view, ordered so the script runs top to bottom.
+pub fn load_schema_script(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+) -> mysql::Result<String> {
+    let table_names: Vec<String> = sql::query_table_outline(conn, String::from(db_name))?
+        .into_iter()
+        .map(|t| t.table_name)
+        .collect();
+    let foreign_keys: Vec<ForeignKey> = sql::query_foreign_keys(conn, String::from(db_name))?;
+    let views: Vec<(String, ViewDependencies)> = sql::query_views(conn, db_name, None)?
+        .into_iter()
+        .map(|v| (v.view_name.clone(), view::dependencies(db_name, &v.view_definition)))
+        .collect();
+
+    let mut statements: Vec<(String, String)> = vec![];
+    for name in table_order(&table_names, &foreign_keys) {
+        if let Some(d) = sql::query_create_table(conn, db_name, &name)? {
+            statements.push((name, d));
+        }
+    }
+    for name in view_order(&views) {
+        if let Some(d) = sql::query_create_view(conn, db_name, &name)? {
+            statements.push((name, d));
+        }
+    }
+
+    Ok(schema_script(db_name, &statements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fk(table: &str, parent: &str) -> ForeignKey {
+        ForeignKey {
+            constraint_name: format!("fk_{}_{}", table, parent),
+            table_name: table.to_string(),
+            column_names: vec![format!("{}_id", parent)],
+            referenced_table_name: parent.to_string(),
+            referenced_column_names: vec![String::from("id")],
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn tables_come_after_the_tables_they_reference() {
+        let tables = names(&["order_items", "orders", "customers", "products"]);
+        let fks = vec![fk("order_items", "orders"), fk("order_items", "products"), fk("orders", "customers")];
+
+        assert_eq!(table_order(&tables, &fks), names(&["customers", "products", "orders", "order_items"]));
+    }
+
+    #[test]
+    fn self_references_and_unknown_tables_are_ignored() {
+        let tables = names(&["categories", "products"]);
+        let fks = vec![fk("categories", "categories"), fk("products", "categories"), fk("products", "suppliers")];
+
+        assert_eq!(table_order(&tables, &fks), names(&["categories", "products"]));
+    }
+
+    #[test]
+    fn cycles_are_broken_alphabetically() {
+        let tables = names(&["b", "a", "c", "d"]);
+        let fks = vec![fk("a", "b"), fk("b", "a"), fk("c", "a"), fk("d", "c")];
+
+        assert_eq!(table_order(&tables, &fks), names(&["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn views_come_after_the_views_they_read() {
+        let deps = |tables: &[&str]| ViewDependencies { table_names: names(tables), columns: vec![] };
+        let views = vec![
+            (String::from("big_orders"), deps(&["order_totals"])),
+            (String::from("order_totals"), deps(&["orders"])),
+            (String::from("active"), deps(&["active", "customers"])),
+        ];
+
+        assert_eq!(view_order(&views), names(&["active", "order_totals", "big_orders"]));
+    }
+
+    #[test]
+    fn script_disables_foreign_key_checks() {
+        let script = schema_script("shop", &[(String::from("orders"), String::from("CREATE TABLE `orders` (`id` int)"))]);
+
+        assert_eq!(script, "-- Schema `shop`\n\nSET FOREIGN_KEY_CHECKS = 0;\n\n\
+            -- orders\nCREATE TABLE `orders` (`id` int);\n\nSET FOREIGN_KEY_CHECKS = 1;\n");
+    }
+}
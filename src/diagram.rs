@@ -0,0 +1,53 @@
+use crate::cardinality::{
+    self,
+    Junction,
+};
+use crate::sql::{
+    ForeignKey,
+    SchemaColumn,
+    UniqueKey,
+};
+
+pub fn mermaid_er(
+    table_names: &[String],
+    foreign_keys: &[ForeignKey],
+    unique_keys: &[UniqueKey],
+    columns: &[SchemaColumn],
+) -> String {
+    let junctions: Vec<Junction> = cardinality::junction_tables(foreign_keys, unique_keys);
+    let mut er = String::from("erDiagram\n");
+
+    for table_name in table_names {
+        er.push_str(&format!("    {} {{\n", entity_name(table_name)));
+        for col in columns.iter().filter(|c| &c.table_name == table_name) {
+            er.push_str(&format!(
+                "        {} {}{}\n",
+                entity_name(&col.data_type),
+                entity_name(&col.column_name),
+                if col.column_key == "PRI" { " PK" } else { "" },
+            ));
+        }
+        er.push_str("    }\n");
+    }
+
+    for fk in foreign_keys {
+        let card = cardinality::of_foreign_key(fk, unique_keys, columns);
+        let junction = junctions.iter().any(|j| j.table_name == fk.table_name);
+        er.push_str(&format!(
+            "    {} {} {} : \"{}{}\"\n",
+            entity_name(&fk.referenced_table_name),
+            card.crows_foot(),
+            entity_name(&fk.table_name),
+            fk.constraint_name.replace('"', "'"),
+            if junction { " (M:N)" } else { "" },
+        ));
+    }
+
+    er
+}
+
+fn entity_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::snapshot::Snapshot;
+use crate::sql::{
+    IndexColumn,
+    Partitioning,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ObjectKind {
+    Table,
+    Column,
+    CheckConstraint,
+    Index,
+    ForeignKey,
+    View,
+    Routine,
+    Trigger,
+}
+
+// `before` and `after` are one-line descriptions of the object, so a change can be
+// read without looking up both snapshots.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Change {
+    pub change: ChangeKind,
+    pub object: ObjectKind,
+    pub name: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl ObjectKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ObjectKind::Table => "table",
+            ObjectKind::Column => "column",
+            ObjectKind::CheckConstraint => "check constraint",
+            ObjectKind::Index => "index",
+            ObjectKind::ForeignKey => "foreign key",
+            ObjectKind::View => "view",
+            ObjectKind::Routine => "routine",
+            ObjectKind::Trigger => "trigger",
+        }
+    }
+}
+
+type Descriptions = BTreeMap<String, String>;
+type Describe = fn(&Snapshot) -> Descriptions;
+
+pub fn diff(before: &Snapshot, after: &Snapshot) -> Vec<Change> {
+    let describers: [(ObjectKind, Describe); 8] = [
+        (ObjectKind::Table, tables),
+        (ObjectKind::Column, columns),
+        (ObjectKind::CheckConstraint, check_constraints),
+        (ObjectKind::Index, indexes),
+        (ObjectKind::ForeignKey, foreign_keys),
+        (ObjectKind::View, views),
+        (ObjectKind::Routine, routines),
+        (ObjectKind::Trigger, triggers),
+    ];
+
+    describers.iter()
+        .flat_map(|(object, describe)| compare(*object, describe(before), describe(after)))
+        .collect()
+}
+
+fn compare(object: ObjectKind, before: Descriptions, after: Descriptions) -> Vec<Change> {
+    let names: Vec<&String> = before.keys().chain(after.keys()).sorted().dedup().collect();
+
+    names.into_iter()
+        .filter_map(|name| {
+            let (b, a) = (before.get(name), after.get(name));
+            let change = match (b, a) {
+                (Some(_), None) => ChangeKind::Removed,
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(b), Some(a)) if b != a => ChangeKind::Changed,
+                _ => return None,
+            };
+            Some(Change {
+                change,
+                object,
+                name: name.clone(),
+                before: b.cloned(),
+                after: a.cloned(),
+            })
+        })
+        .collect()
+}
+
+fn with_comment(description: String, comment: &str) -> String {
+    if comment.is_empty() {
+        description
+    } else {
+        format!("{} COMMENT '{}'", description, comment)
+    }
+}
+
+fn partitioning(partitioning: &Partitioning) -> String {
+    let mut description = format!(
+        " PARTITION BY {} ({})", partitioning.method, partitioning.expression.as_deref().unwrap_or(""));
+    if let Some(ref method) = partitioning.subpartition_method {
+        description.push_str(&format!(
+            " SUBPARTITION BY {} ({})", method, partitioning.subpartition_expression.as_deref().unwrap_or("")));
+    }
+    let values = if partitioning.method.starts_with("RANGE") {
+        "VALUES LESS THAN "
+    } else if partitioning.method.starts_with("LIST") {
+        "VALUES IN "
+    } else {
+        ""
+    };
+    let partitions = partitioning.partitions.iter()
+        .map(|p| match p.partition_description {
+            Some(ref bound) if !values.is_empty() => format!("{} {}({})", p.partition_name, values, bound),
+            _ => p.partition_name.clone(),
+        })
+        .join(", ");
+    description.push_str(&format!(" ({})", partitions));
+    description
+}
+
+fn tables(snapshot: &Snapshot) -> Descriptions {
+    snapshot.tables.iter()
+        .map(|t| {
+            let mut description = String::from("TABLE");
+            if let Some(ref engine) = t.engine {
+                description.push_str(&format!(" ENGINE={}", engine));
+            }
+            if let Some(ref collation) = t.table_collation {
+                description.push_str(&format!(" COLLATE={}", collation));
+            }
+            if let Some(ref options) = t.create_options {
+                description.push_str(&format!(" {}", options));
+            }
+            if let Some(ref p) = t.partitioning {
+                description.push_str(&partitioning(p));
+            }
+            (t.table_name.clone(), with_comment(description, t.table_comment.as_deref().unwrap_or("")))
+        })
+        .collect()
+}
+
+fn columns(snapshot: &Snapshot) -> Descriptions {
+    snapshot.columns.iter()
+        .map(|c| {
+            let mut description = c.column_type.clone();
+            if let Some(ref charset) = c.character_set_name {
+                description.push_str(&format!(" CHARACTER SET {}", charset));
+            }
+            if let Some(ref collation) = c.collation_name {
+                description.push_str(&format!(" COLLATE {}", collation));
+            }
+            if let Some(ref expression) = c.generation_expression {
+                description.push_str(&format!(" AS ({})", expression));
+            }
+            if !c.nullable {
+                description.push_str(" NOT NULL");
+            }
+            if let Some(ref default) = c.column_default {
+                description.push_str(&format!(" DEFAULT {}", default));
+            }
+            if !c.extra.is_empty() {
+                description.push_str(&format!(" {}", c.extra.to_uppercase()));
+            }
+            if !c.column_key.is_empty() {
+                description.push_str(&format!(" KEY {}", c.column_key));
+            }
+            (format!("{}.{}", c.table_name, c.column_name), with_comment(description, &c.column_comment))
+        })
+        .collect()
+}
+
+fn check_constraints(snapshot: &Snapshot) -> Descriptions {
+    snapshot.tables.iter()
+        .flat_map(|t| t.check_constraints.iter().map(move |c| (
+            format!("{}.{}", t.table_name, c.constraint_name),
+            format!("CHECK ({}){}", c.check_clause, if c.enforced { "" } else { " NOT ENFORCED" }),
+        )))
+        .collect()
+}
+
+fn index_column(column: &IndexColumn) -> String {
+    let name = column.column_name.as_deref().unwrap_or("(expression)");
+    match column.sub_part {
+        Some(length) => format!("{}({})", name, length),
+        None => String::from(name),
+    }
+}
+
+fn indexes(snapshot: &Snapshot) -> Descriptions {
+    snapshot.indexes.iter()
+        .map(|i| {
+            let kind = if i.primary { "PRIMARY" } else if i.unique { "UNIQUE" } else { "INDEX" };
+            let mut description = format!(
+                "{} {} ({})", kind, i.index_type, i.columns.iter().map(index_column).join(", "));
+            if !i.visible {
+                description.push_str(" INVISIBLE");
+            }
+            (format!("{}.{}", i.table_name, i.index_name), description)
+        })
+        .collect()
+}
+
+fn foreign_keys(snapshot: &Snapshot) -> Descriptions {
+    snapshot.foreign_keys.iter()
+        .map(|fk| (
+            format!("{}.{}", fk.table_name, fk.constraint_name),
+            format!(
+                "({}) REFERENCES {} ({})",
+                fk.column_names.join(", "), fk.referenced_table_name, fk.referenced_column_names.join(", "),
+            ),
+        ))
+        .collect()
+}
+
+fn views(snapshot: &Snapshot) -> Descriptions {
+    snapshot.views.iter()
+        .map(|v| (
+            v.view_name.clone(),
+            format!("SQL SECURITY {} CHECK OPTION {} AS {}", v.security_type, v.check_option, v.view_definition),
+        ))
+        .collect()
+}
+
+// Keyed by type as well, since a procedure and a function may share a name.
+fn routines(snapshot: &Snapshot) -> Descriptions {
+    snapshot.routines.iter()
+        .map(|r| {
+            let parameters = r.parameters.iter()
+                .map(|p| format!("{} {} {}", p.parameter_mode.as_deref().unwrap_or(""), p.parameter_name, p.parameter_type)
+                    .trim()
+                    .to_string())
+                .join(", ");
+            let returns = r.return_type.as_ref().map(|t| format!(" RETURNS {}", t)).unwrap_or_default();
+            (
+                format!("{} {}", r.routine_type, r.routine_name),
+                format!(
+                    "({}){} {}",
+                    parameters, returns, r.routine_definition.as_deref().unwrap_or(""),
+                ).trim_end().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn triggers(snapshot: &Snapshot) -> Descriptions {
+    snapshot.triggers.iter()
+        .map(|t| (
+            format!("{}.{}", t.table_name, t.trigger_name),
+            format!("{} {} {}", t.action_timing, t.event_manipulation, t.action_statement),
+        ))
+        .collect()
+}
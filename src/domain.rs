@@ -0,0 +1,110 @@
+pub fn enum_values(column_type: &str) -> Vec<String> {
+    let lower = column_type.to_lowercase();
+    let body = if lower.starts_with("enum(") {
+        &column_type[5..]
+    } else if lower.starts_with("set(") {
+        &column_type[4..]
+    } else {
+        return vec![];
+    };
+
+    let chars: Vec<char> = body.chars().collect();
+    let mut values: Vec<String> = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '\'' {
+            i += 1;
+            continue;
+        }
+
+        let mut value = String::new();
+        i += 1;
+        while i < chars.len() {
+            if chars[i] == '\'' {
+                if i + 1 < chars.len() && chars[i + 1] == '\'' {
+                    value.push('\'');
+                    i += 2;
+                    continue;
+                }
+                break;
+            }
+            if chars[i] == '\\' && i + 1 < chars.len() {
+                value.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            value.push(chars[i]);
+            i += 1;
+        }
+        values.push(value);
+        i += 1;
+    }
+
+    values
+}
+
+pub fn mentioned_columns(check_clause: &str, column_names: &[String]) -> Vec<String> {
+    let chars: Vec<char> = check_clause.chars().collect();
+    let mut identifiers: Vec<String> = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' || c == '"' || c == '`' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && c != '`' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            if c == '`' {
+                identifiers.push(chars[start..i.min(chars.len())].iter().collect());
+            }
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            identifiers.push(chars[start..i].iter().collect());
+        } else {
+            i += 1;
+        }
+    }
+
+    column_names.iter()
+        .filter(|name| identifiers.iter().any(|ident| ident.eq_ignore_ascii_case(name)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn enum_and_set_values_are_unquoted() {
+        assert_eq!(enum_values("enum('new','paid','shipped')"), names(&["new", "paid", "shipped"]));
+        assert_eq!(enum_values("SET('a','b')"), names(&["a", "b"]));
+        assert_eq!(enum_values("enum('it''s','a,b','back\\\\slash','')"), names(&["it's", "a,b", "back\\slash", ""]));
+        assert!(enum_values("varchar(255)").is_empty());
+        assert!(enum_values("int(11)").is_empty());
+    }
+
+    #[test]
+    fn check_clause_mentions_quoted_and_bare_columns() {
+        let columns = names(&["price", "discount", "status", "note"]);
+
+        assert_eq!(mentioned_columns("(`price` > 0) and (`Discount` <= `price`)", &columns), names(&["price", "discount"]));
+        assert_eq!(mentioned_columns("status in ('note', 'price')", &columns), names(&["status"]));
+        assert_eq!(mentioned_columns("`status` <> 'it\\'s `note`'", &columns), names(&["status"]));
+        assert!(mentioned_columns("prices > 0", &columns).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+
+use actix_web::{
+    dev::HttpResponseBuilder,
+    error::{
+        BlockingError,
+        ResponseError,
+    },
+    http::{
+        header,
+        StatusCode,
+    },
+    HttpResponse,
+};
+
+use crate::sql;
+
+const ERROR_PAGE: &str = include_str!("../templates/error.html");
+const RETRY_AFTER_SECS: &str = "5";
+
+#[derive(Debug)]
+pub enum AppError {
+    Config(String),
+    Connection(r2d2::Error),
+    Query(mysql::Error),
+    Timeout(mysql::Error),
+    Decode(mysql::Error),
+    Template(tera::Error),
+    Io(String),
+    Input(String),
+    Canceled,
+}
+
+// The same errors rendered as a JSON body, for API routes.
+#[derive(Debug)]
+pub struct JsonError(pub AppError);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ErrorBody {
+    pub error: String,
+    pub status: u16,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Config(_) => "config",
+            AppError::Connection(_) => "connection",
+            AppError::Query(_) => "query",
+            AppError::Timeout(_) => "timeout",
+            AppError::Decode(_) => "decode",
+            AppError::Template(_) => "template",
+            AppError::Io(_) => "io",
+            AppError::Input(_) => "input",
+            AppError::Canceled => "canceled",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Connection(_) | AppError::Canceled => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Exit statuses from sysexits.h, for the command line.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Config(_) => 78,
+            AppError::Connection(_) | AppError::Canceled => 69,
+            AppError::Timeout(_) => 75,
+            AppError::Input(_) => 65,
+            AppError::Io(_) => 74,
+            _ => 70,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            AppError::Config(_) => "Invalid configuration",
+            AppError::Connection(_) => "Database unavailable",
+            AppError::Query(_) => "Query failed",
+            AppError::Timeout(_) => "Query timed out",
+            AppError::Decode(_) => "Unexpected query result",
+            AppError::Template(_) => "Page could not be rendered",
+            AppError::Io(_) => "File could not be accessed",
+            AppError::Input(_) => "Invalid input",
+            AppError::Canceled => "Request canceled",
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        match self {
+            AppError::Connection(_) | AppError::Canceled =>
+                "The database could not be reached. Try again in a moment.",
+            AppError::Timeout(_) =>
+                "The database did not answer in time, so the query was cancelled on the server. \
+                 The schema may be very large or the server busy; try again in a moment.",
+            _ => "The request failed. The details below may help to find the cause.",
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            error: String::from(self.kind()),
+            status: self.status().as_u16(),
+            message: self.to_string(),
+        }
+    }
+
+    fn response(&self) -> HttpResponseBuilder {
+        let mut response = HttpResponse::build(self.status());
+        if self.status() == StatusCode::SERVICE_UNAVAILABLE {
+            response.header(header::RETRY_AFTER, RETRY_AFTER_SECS);
+        }
+        response
+    }
+
+    // Rendered without the application's Tera instance, so template errors still get a page.
+    fn page(&self) -> String {
+        let mut ctx = tera::Context::new();
+        ctx.insert("title", self.title());
+        ctx.insert("status", &self.status().as_u16());
+        ctx.insert("hint", self.hint());
+        ctx.insert("message", &self.to_string());
+
+        tera::Tera::one_off(ERROR_PAGE, &ctx, true)
+            .unwrap_or_else(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Config(e) => write!(f, "Invalid configuration: {}", e),
+            AppError::Connection(e) => write!(f, "Could not get a database connection: {}", e),
+            AppError::Query(e) => write!(f, "Database query failed: {}", e),
+            AppError::Timeout(e) => write!(f, "Database query timed out: {}", e),
+            AppError::Decode(e) => write!(f, "Could not decode a query result: {}", e),
+            AppError::Template(e) => write!(f, "Template error: {}", e),
+            AppError::Io(e) => write!(f, "{}", e),
+            AppError::Input(e) => write!(f, "Invalid input: {}", e),
+            AppError::Canceled => write!(f, "Database work was canceled"),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<mysql::Error> for AppError {
+    fn from(e: mysql::Error) -> AppError {
+        match e {
+            mysql::Error::FromValueError(_) | mysql::Error::FromRowError(_) => AppError::Decode(e),
+            _ if sql::is_timeout(&e) => AppError::Timeout(e),
+            _ => AppError::Query(e),
+        }
+    }
+}
+
+impl From<r2d2::Error> for AppError {
+    fn from(e: r2d2::Error) -> AppError {
+        AppError::Connection(e)
+    }
+}
+
+impl From<tera::Error> for AppError {
+    fn from(e: tera::Error) -> AppError {
+        AppError::Template(e)
+    }
+}
+
+impl From<BlockingError<AppError>> for AppError {
+    fn from(e: BlockingError<AppError>) -> AppError {
+        match e {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => AppError::Canceled,
+        }
+    }
+}
+
+impl From<AppError> for JsonError {
+    fn from(e: AppError) -> JsonError {
+        JsonError(e)
+    }
+}
+
+impl ResponseError for AppError {
+    fn error_response(&self) -> HttpResponse {
+        self.response()
+            .content_type("text/html")
+            .body(self.page())
+    }
+
+    // The default renders the Display text over the body built above.
+    fn render_response(&self) -> HttpResponse {
+        self.error_response()
+    }
+}
+
+impl ResponseError for JsonError {
+    fn error_response(&self) -> HttpResponse {
+        self.0.response().json(self.0.body())
+    }
+
+    fn render_response(&self) -> HttpResponse {
+        self.error_response()
+    }
+}
@@ -1,77 +1,144 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
 
 use actix_web::{
-    error,
     Error,
     get,
+    http,
+    HttpRequest,
     HttpResponse,
     post,
     web
 };
-
-use sql::TableOutlineResult;
+use futures::Future;
+use r2d2::PooledConnection;
+use r2d2_mysql::MysqlConnectionManager;
 
 use crate::{
+    autocomplete,
+    catalog,
     datasource,
+    ddl,
+    diagram,
+    inference,
+    joinpath,
+    lint,
+    page,
+    rowcount,
     sql,
 };
-use crate::sql::{Table, TableSizeResult};
+use crate::catalog::Catalog;
+use crate::conditional::{
+    PageVersions,
+    Validator,
+};
+use crate::error::{
+    AppError,
+    JsonError,
+};
+use crate::inference::InferredRelation;
+use crate::joinpath::JoinPath;
+use crate::lint::Finding;
+use crate::page::ViewOutline;
+use crate::search::SearchHit;
+use crate::settings::Features;
+use crate::sql::{Event, ForeignKey, Index, Routine, Table, Trigger, View};
 
 pub struct AppConfig {
     pub datasource: datasource::DataSource,
     pub tera: tera::Tera,
+    pub catalog: catalog::CatalogCache,
+    pub row_counts: rowcount::RowCountCache,
+    pub page_versions: PageVersions,
+    pub row_count_timeout_ms: u64,
+    pub features: Features,
 }
 
+// Readiness probes must answer well within the orchestrator's probe timeout.
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[derive(Serialize, Deserialize)]
-struct TableOutline {
-    fqn: String,
-    name: String,
-    column_count: u32,
-    row_count: u64,
-    parent_count: u32,
-    child_count: u32,
-    comment: String,
-    note: String
+struct Readiness {
+    ready: bool,
+    database: bool,
+    catalog: bool,
+}
+
+// Runs database work on the blocking thread pool, so a slow query holds up only its
+// own request instead of every request on the same worker.
+fn blocking<F, T>(
+    config: &web::Data<AppConfig>,
+    f: F,
+) -> impl Future<Item=T, Error=AppError>
+    where F: FnOnce(&AppConfig) -> Result<T, AppError> + Send + 'static, T: Send + 'static {
+    let config = config.clone();
+    web::block(move || f(&config)).from_err()
+}
+
+fn conn(config: &AppConfig) -> Result<PooledConnection<MysqlConnectionManager>, AppError> {
+    Ok(config.datasource.conn_pool.get()?)
+}
+
+fn load_catalog(config: &AppConfig) -> Result<Arc<Catalog>, AppError> {
+    config.catalog.get(&config.datasource.conn_pool, &config.datasource.name)
+}
+
+fn catalog(config: &web::Data<AppConfig>) -> impl Future<Item=Arc<Catalog>, Error=AppError> {
+    blocking(config, load_catalog)
+}
+
+// Compiled once at startup by `serve`, and by `export --format site`.
+pub const TEMPLATES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*");
+
+// The templates with the filters they use. Names in links are written
+// `{{ name | safe | path_segment }}`: Tera escapes HTML before the filters unless the
+// first one is `safe`, and the encoded segment has nothing left to escape.
+pub fn templates() -> tera::Result<tera::Tera> {
+    let mut tera = tera::Tera::new(TEMPLATES)?;
+    tera.register_filter("path_segment", path_segment_filter);
+    Ok(tera)
+}
+
+fn path_segment_filter(value: tera::Value, _: HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    match value.as_str() {
+        Some(name) => Ok(tera::Value::String(page::path_segment(name))),
+        None => Err(tera::Error::from("path_segment expects a string")),
+    }
+}
+
+fn render_html(config: &AppConfig, template: &str, ctx: &tera::Context) -> Result<String, AppError> {
+    Ok(config.tera.render(template, ctx)?)
 }
 
 #[get("/tables")]
 pub fn get_tables(
     config: web::Data<AppConfig>,
-) -> Result<HttpResponse, Error> {
-    let pool = &config.datasource.conn_pool.clone();
-    let mut conn = pool.get().unwrap();
-    let db_name = &config.datasource.name;
+    req: HttpRequest,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    catalog(&config).and_then(move |catalog| tables_page(&config, &req, &catalog))
+}
+
+fn tables_page(
+    config: &AppConfig,
+    req: &HttpRequest,
+    catalog: &Catalog,
+) -> Result<HttpResponse, AppError> {
+    let validator = Validator::new(&catalog.version)
+        .with(&config.row_counts.changes(), config.row_counts.modified_epoch());
+    if let Some(not_modified) = validator.not_modified(req) {
+        return Ok(not_modified);
+    }
+
+    let ctx = page::tables(&config.datasource.name, catalog, &config.row_counts, config.features);
 
-    let table_results: Vec<TableOutlineResult> = sql::query_table_outline(
-        &mut conn, String::from(db_name));
-    let size_map: HashMap<String, TableSizeResult> = sql::query_table_size_map(
-        &mut conn, String::from(db_name));
-    let ref_parent_map: HashMap<String, u32> = sql::query_table_referencing_count_to_parent_map(
-        &mut conn, String::from(db_name));
-    let ref_children_map: HashMap<String, u32> = sql::query_table_referenced_count_from_children_map(
-        &mut conn, String::from(db_name));
-
-    let tables: Vec<TableOutline> = table_results.into_iter().map(|t| {
-        let table_name: &str = t.table_name.as_str();
-        TableOutline {
-            fqn: format!("{}.{}", db_name, table_name),
-            name: table_name.to_string(),
-            column_count: size_map[table_name].columns,
-            row_count: size_map[table_name].rows,
-            parent_count: if ref_parent_map.contains_key(table_name) { ref_parent_map[table_name] } else { 0 },
-            child_count: if ref_children_map.contains_key(table_name) { ref_children_map[table_name] } else { 0 },
-            comment: t.table_comment.unwrap_or(String::from("")),
-            note: String::from("NOTEnote")
-        }
-    }).collect();
-
-    let mut ctx = tera::Context::new();
-    ctx.insert("tables", &tables);
-
-    let s = config.tera.render("tables.html", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("Template error"))?;
-
-    Ok(HttpResponse::Ok()
+    let s = render_html(config, "tables.html", &ctx)?;
+
+    Ok(validator.headers(&mut HttpResponse::Ok())
         .content_type("text/html")
         .body(s)
     )
@@ -85,19 +152,32 @@ pub struct TablePathVariable {
 #[get("/table/{table_name}")]
 pub fn get_table_by_name(
     config: web::Data<AppConfig>,
+    req: HttpRequest,
     path_var: web::Path<TablePathVariable>,
-) -> Result<HttpResponse, Error> {
-    let pool = &config.datasource.conn_pool.clone();
-    let conn = pool.get().unwrap();
-    let db_name = &config.datasource.name;
-    let table_name = &path_var.table_name;
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    let table_name = path_var.into_inner().table_name;
 
-    let table_opt: Option<Table> = sql::query_table(
-        conn,
-        String::from(db_name),
-        String::from(table_name),
-    );
+    catalog(&config).and_then(move |catalog| {
+        blocking(&config, move |config| {
+            let mut conn = conn(config)?;
+            let db_name = &config.datasource.name;
+            let views: Vec<View> = sql::query_views(&mut conn, db_name, None)?;
+            let table_opt: Option<Table> = sql::query_table(
+                &mut conn, db_name, &table_name, config.features.partition_max)?;
+            Ok((views, table_opt))
+        }).and_then(move |(views, table_opt)| {
+            table_page(&config, &req, &catalog, &views, table_opt)
+        })
+    })
+}
 
+fn table_page(
+    config: &AppConfig,
+    req: &HttpRequest,
+    catalog: &Catalog,
+    views: &[View],
+    table_opt: Option<Table>,
+) -> Result<HttpResponse, AppError> {
     let table = match table_opt {
         Some(t) => t,
         _ => {
@@ -105,14 +185,636 @@ pub fn get_table_by_name(
         }
     };
 
-    let mut ctx = tera::Context::new();
-    ctx.insert("table", &table);
-
-    let s = config.tera.render("table.html", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("Template error"))?;
+    let now_epoch = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (validator, s) = render_table(
+        &config.tera, &config.datasource.name, catalog, views, table, &config.page_versions, now_epoch)?;
+    if let Some(not_modified) = validator.not_modified(req) {
+        return Ok(not_modified);
+    }
 
-    Ok(HttpResponse::Ok()
+    Ok(validator.headers(&mut HttpResponse::Ok())
         .content_type("text/html")
         .body(s)
     )
 }
+
+// The table page shows live data besides the catalog, so its validator is taken from
+// the rendered page rather than from the catalog version.
+pub fn render_table(
+    tera: &tera::Tera,
+    db_name: &str,
+    catalog: &Catalog,
+    views: &[View],
+    table: Table,
+    page_versions: &PageVersions,
+    now_epoch: u64,
+) -> Result<(Validator, String), AppError> {
+    let page = format!("/table/{}", table.table_name);
+    let ctx = page::table(db_name, catalog, views, table);
+    let s = tera.render("table.html", &ctx)?;
+
+    Ok((page_versions.validator(&page, &s, now_epoch), s))
+}
+
+fn mermaid_diagram(catalog: &Catalog) -> String {
+    diagram::mermaid_er(&catalog.table_names(), &catalog.foreign_keys, &catalog.unique_keys, &catalog.columns)
+}
+
+#[get("/diagram")]
+pub fn get_diagram(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    catalog(&config).and_then(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return Ok(not_modified);
+        }
+
+        let mut ctx = tera::Context::new();
+        ctx.insert("mermaid", &mermaid_diagram(&catalog));
+
+        let s = render_html(&config, "diagram.html", &ctx)?;
+
+        Ok(validator.headers(&mut HttpResponse::Ok())
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/diagram.mmd")]
+pub fn get_diagram_mermaid(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    catalog(&config).map(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return not_modified;
+        }
+
+        validator.headers(&mut HttpResponse::Ok())
+            .content_type("text/plain; charset=utf-8")
+            .body(mermaid_diagram(&catalog))
+    })
+}
+
+fn infer_relations(
+    config: &AppConfig,
+    catalog: &Catalog,
+    sample: bool,
+) -> Result<Vec<InferredRelation>, AppError> {
+    let mut inferred = catalog.inferred_relations.clone();
+    if sample {
+        let conn = &mut conn(config)?;
+        let db_name = &config.datasource.name;
+        for relation in inferred.iter_mut() {
+            // A failed sample, such as a permission error on one table, is shown on its
+            // relation instead of failing the whole page.
+            match sql::query_value_containment(conn, db_name, relation, inference::CONTAINMENT_SAMPLE_SIZE) {
+                Ok(Some(c)) => inference::apply_containment(relation, c),
+                Ok(None) => (),
+                Err(e) => relation.sample_error = Some(e.to_string()),
+            }
+        }
+        inference::sort_relations(&mut inferred);
+    }
+
+    Ok(inferred)
+}
+
+fn inferred_relations(
+    config: &web::Data<AppConfig>,
+    sample: bool,
+) -> impl Future<Item=(Validator, Vec<InferredRelation>), Error=AppError> {
+    blocking(config, move |config| {
+        let catalog = load_catalog(config)?;
+        let inferred = infer_relations(config, &catalog, sample)?;
+        Ok((Validator::new(&catalog.version), inferred))
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InferredRelationQuery {
+    sample: Option<bool>,
+}
+
+#[get("/relations/inferred")]
+pub fn get_inferred_relations(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<InferredRelationQuery>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    let sample = query.sample.unwrap_or(false);
+
+    inferred_relations(&config, sample).and_then(move |(validator, inferred)| {
+        // Sampled containment depends on table data, which the catalog version does not track.
+        if !sample {
+            if let Some(not_modified) = validator.not_modified(&req) {
+                return Ok(not_modified);
+            }
+        }
+
+        let mut ctx = tera::Context::new();
+        ctx.insert("relations", &inferred);
+        ctx.insert("sample", &sample);
+
+        let s = render_html(&config, "inferred.html", &ctx)?;
+
+        let mut response = HttpResponse::Ok();
+        if !sample {
+            validator.headers(&mut response);
+        }
+        Ok(response
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/api/relations/inferred")]
+pub fn get_inferred_relations_json(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<InferredRelationQuery>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    let sample = query.sample.unwrap_or(false);
+
+    inferred_relations(&config, sample).map(move |(validator, inferred)| {
+        if !sample {
+            if let Some(not_modified) = validator.not_modified(&req) {
+                return not_modified;
+            }
+        }
+
+        let mut response = HttpResponse::Ok();
+        if !sample {
+            validator.headers(&mut response);
+        }
+        response.json(inferred)
+    }).map_err(JsonError)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JoinPathQuery {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+fn find_join_paths(
+    catalog: &Catalog,
+    query: &JoinPathQuery,
+) -> Vec<JoinPath> {
+    let (from, to) = match (&query.from, &query.to) {
+        (Some(f), Some(t)) => (f, t),
+        _ => return vec![],
+    };
+
+    joinpath::find_shortest_paths(&catalog.foreign_keys, from, to, joinpath::JOIN_PATH_LIMIT)
+}
+
+#[get("/joins")]
+pub fn get_joins(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<JoinPathQuery>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    catalog(&config).and_then(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return Ok(not_modified);
+        }
+
+        let paths = find_join_paths(&catalog, &query);
+        let table_names: Vec<String> = catalog.table_names();
+
+        let mut ctx = tera::Context::new();
+        ctx.insert("table_names", &table_names);
+        ctx.insert("from", &query.from);
+        ctx.insert("to", &query.to);
+        ctx.insert("paths", &paths);
+
+        let s = render_html(&config, "joins.html", &ctx)?;
+
+        Ok(validator.headers(&mut HttpResponse::Ok())
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/api/joins")]
+pub fn get_joins_json(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<JoinPathQuery>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    catalog(&config).map(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return not_modified;
+        }
+
+        let paths = find_join_paths(&catalog, &query);
+
+        validator.headers(&mut HttpResponse::Ok()).json(paths)
+    }).map_err(JsonError)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SearchQuery {
+    q: Option<String>,
+}
+
+fn search(
+    catalog: &Catalog,
+    query: &str,
+) -> Vec<SearchHit> {
+    catalog.search_index.search(query)
+}
+
+#[get("/search")]
+pub fn get_search(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<SearchQuery>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    catalog(&config).and_then(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return Ok(not_modified);
+        }
+
+        let q = query.q.clone().unwrap_or_default();
+        let hits = search(&catalog, &q);
+
+        let mut ctx = tera::Context::new();
+        ctx.insert("q", &q);
+        ctx.insert("hits", &hits);
+
+        let s = render_html(&config, "search.html", &ctx)?;
+
+        Ok(validator.headers(&mut HttpResponse::Ok())
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/api/search")]
+pub fn get_search_json(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<SearchQuery>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    catalog(&config).map(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return not_modified;
+        }
+
+        let q = query.q.clone().unwrap_or_default();
+
+        validator.headers(&mut HttpResponse::Ok()).json(search(&catalog, &q))
+    }).map_err(JsonError)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AutocompleteQuery {
+    prefix: Option<String>,
+    tables: Option<String>,
+}
+
+#[get("/api/autocomplete")]
+pub fn get_autocomplete_json(
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    query: web::Query<AutocompleteQuery>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    catalog(&config).map(move |catalog| {
+        let validator = Validator::new(&catalog.version);
+        if let Some(not_modified) = validator.not_modified(&req) {
+            return not_modified;
+        }
+        let context: Vec<String> = query.tables.as_ref()
+            .map(|t| t.split(',').map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect())
+            .unwrap_or_default();
+
+        validator.headers(&mut HttpResponse::Ok()).json(autocomplete::complete(
+            &catalog, query.prefix.as_deref().unwrap_or(""), &context))
+    }).map_err(JsonError)
+}
+
+fn schema_health(config: &AppConfig) -> Result<Vec<Finding>, AppError> {
+    let mut conn = conn(config)?;
+    let db_name = &config.datasource.name;
+
+    let foreign_keys: Vec<ForeignKey> = sql::query_foreign_keys(&mut conn, String::from(db_name))?;
+    let indexes: Vec<Index> = sql::query_indexes(&mut conn, db_name, None)?;
+
+    Ok(lint::lint(&foreign_keys, &indexes))
+}
+
+#[get("/schema-health")]
+pub fn get_schema_health(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    blocking(&config, schema_health).and_then(move |findings| {
+        let mut ctx = tera::Context::new();
+        ctx.insert("findings", &findings);
+
+        let s = render_html(&config, "health.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/api/schema-health")]
+pub fn get_schema_health_json(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    blocking(&config, schema_health)
+        .map(|findings| HttpResponse::Ok().json(findings))
+        .map_err(JsonError)
+}
+
+fn view_outlines(config: &AppConfig, view_name: Option<&str>) -> Result<(Vec<ViewOutline>, Vec<String>), AppError> {
+    let mut conn = conn(config)?;
+    let db_name = &config.datasource.name;
+
+    let views: Vec<View> = sql::query_views(&mut conn, db_name, view_name)?;
+    let view_names: Vec<String> = match view_name {
+        Some(_) => sql::query_views(&mut conn, db_name, None)?.into_iter().map(|v| v.view_name).collect(),
+        _ => views.iter().map(|v| v.view_name.clone()).collect(),
+    };
+
+    Ok((page::view_outlines(db_name, views), view_names))
+}
+
+#[get("/views")]
+pub fn get_views(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    blocking(&config, |config| view_outlines(config, None)).and_then(move |(views, _)| {
+        let mut ctx = tera::Context::new();
+        ctx.insert("views", &views);
+
+        let s = render_html(&config, "views.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ViewPathVariable {
+    view_name: String,
+}
+
+#[get("/view/{view_name}")]
+pub fn get_view_by_name(
+    config: web::Data<AppConfig>,
+    path_var: web::Path<ViewPathVariable>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    let view_name = path_var.into_inner().view_name;
+
+    blocking(&config, move |config| {
+        let (mut views, view_names) = view_outlines(config, Some(&view_name))?;
+
+        let view = match views.pop() {
+            Some(v) => v,
+            _ => return Ok(None),
+        };
+
+        let mut conn = conn(config)?;
+        let ddl = sql::query_create_view(&mut conn, &config.datasource.name, &view.view.view_name)?;
+
+        Ok(Some((view, view_names, ddl)))
+    }).and_then(move |found| {
+        let (view, view_names, ddl) = match found {
+            Some(f) => f,
+            _ => {
+                return Ok(HttpResponse::NotFound().body(""));
+            }
+        };
+
+        let ctx = page::view(&view, &view_names, &ddl);
+
+        let s = render_html(&config, "view.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/routines")]
+pub fn get_routines(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    blocking(&config, |config| {
+        Ok(sql::query_routines(&mut conn(config)?, &config.datasource.name, None)?)
+    }).and_then(move |routines: Vec<Routine>| {
+        let mut ctx = tera::Context::new();
+        ctx.insert("routines", &routines);
+
+        let s = render_html(&config, "routines.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RoutinePathVariable {
+    routine_name: String,
+}
+
+#[get("/routine/{routine_name}")]
+pub fn get_routine_by_name(
+    config: web::Data<AppConfig>,
+    path_var: web::Path<RoutinePathVariable>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    let routine_name = path_var.into_inner().routine_name;
+    let name = routine_name.clone();
+
+    blocking(&config, move |config| {
+        Ok(sql::query_routines(&mut conn(config)?, &config.datasource.name, Some(&name))?)
+    }).and_then(move |routines: Vec<Routine>| {
+        if routines.is_empty() {
+            return Ok(HttpResponse::NotFound().body(""));
+        }
+
+        let mut ctx = tera::Context::new();
+        ctx.insert("routine_name", &routine_name);
+        ctx.insert("routines", &routines);
+
+        let s = render_html(&config, "routine.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/triggers")]
+pub fn get_triggers(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    blocking(&config, |config| {
+        Ok(sql::query_triggers(&mut conn(config)?, &config.datasource.name, None)?)
+    }).and_then(move |triggers: Vec<Trigger>| {
+        let mut ctx = tera::Context::new();
+        ctx.insert("triggers", &triggers);
+
+        let s = render_html(&config, "triggers.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/events")]
+pub fn get_events(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    blocking(&config, |config| {
+        Ok(sql::query_events(&mut conn(config)?, &config.datasource.name)?)
+    }).and_then(move |events: Vec<Event>| {
+        let mut ctx = tera::Context::new();
+        ctx.insert("events", &events);
+
+        let s = render_html(&config, "events.html", &ctx)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/html")
+            .body(s)
+        )
+    })
+}
+
+#[get("/table/{table_name}/ddl")]
+pub fn get_table_ddl(
+    config: web::Data<AppConfig>,
+    path_var: web::Path<TablePathVariable>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    let table_name = path_var.into_inner().table_name;
+
+    blocking(&config, move |config| {
+        let mut conn = conn(config)?;
+        let db_name = &config.datasource.name;
+
+        // Only a name that is neither a table nor a view is a 404; a failed query is
+        // an error response, a 504 when it timed out.
+        match sql::query_create_table(&mut conn, db_name, &table_name)? {
+            Some(d) => Ok(Some(d)),
+            None => Ok(sql::query_create_view(&mut conn, db_name, &table_name)?),
+        }
+    }).map(|ddl| match ddl {
+        Some(d) => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .body(format!("{};\n", d)),
+        _ => HttpResponse::NotFound().body(""),
+    })
+}
+
+fn schema_ddl(config: &AppConfig) -> Result<String, AppError> {
+    let mut conn = conn(config)?;
+    Ok(ddl::load_schema_script(&mut conn, &config.datasource.name)?)
+}
+
+#[get("/ddl")]
+pub fn get_schema_ddl(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    blocking(&config, schema_ddl).map(move |script| {
+        HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .header("Content-Disposition", format!("attachment; filename=\"{}.sql\"", config.datasource.name))
+            .body(script)
+    })
+}
+
+#[post("/row-counts/refresh")]
+pub fn post_row_counts_refresh(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=AppError> {
+    catalog(&config).map(move |catalog| {
+        let pool = &config.datasource.conn_pool;
+        let db_name = &config.datasource.name;
+
+        config.row_counts.refresh(
+            pool.clone(), String::from(db_name), catalog.table_names(), config.row_count_timeout_ms);
+
+        HttpResponse::SeeOther()
+            .header(http::header::LOCATION, "/tables")
+            .finish()
+    })
+}
+
+#[get("/api/row-counts")]
+pub fn get_row_counts_json(
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(config.row_counts.all()))
+}
+
+#[get("/admin/catalog")]
+pub fn get_catalog_status_json(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    catalog(&config)
+        .map(|catalog| HttpResponse::Ok().json(catalog.status()))
+        .map_err(JsonError)
+}
+
+#[get("/admin/pool")]
+pub fn get_pool_stats_json(
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(config.datasource.pool_stats()))
+}
+
+#[post("/admin/catalog/refresh")]
+pub fn post_catalog_refresh(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    blocking(&config, |config| {
+        config.catalog.refresh(&config.datasource.conn_pool, &config.datasource.name)
+    }).map(|catalog| HttpResponse::Ok().json(catalog.status()))
+        .map_err(JsonError)
+}
+
+#[get("/healthz")]
+pub fn get_healthz() -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().content_type("text/plain; charset=utf-8").body("ok"))
+}
+
+#[get("/readyz")]
+pub fn get_readyz(
+    config: web::Data<AppConfig>,
+) -> impl Future<Item=HttpResponse, Error=JsonError> {
+    blocking(&config, |config| {
+        let database = config.datasource.is_reachable(READY_CHECK_TIMEOUT);
+        let catalog = config.catalog.is_loaded();
+        Ok(Readiness { ready: database && catalog, database, catalog })
+    }).map(|readiness| {
+        if readiness.ready {
+            HttpResponse::Ok().json(readiness)
+        } else {
+            HttpResponse::ServiceUnavailable().json(readiness)
+        }
+    }).map_err(JsonError)
+}
@@ -0,0 +1,276 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use crate::sql::{
+    ForeignKey,
+    SchemaColumn,
+};
+
+pub const CONTAINMENT_SAMPLE_SIZE: u32 = 1000;
+
+const CONFIDENCE_NAME_MATCH: u32 = 50;
+const CONFIDENCE_PRIMARY_KEY: u32 = 20;
+const CONFIDENCE_SAME_COLUMN_TYPE: u32 = 20;
+const CONFIDENCE_SAME_DATA_TYPE: u32 = 10;
+const CONFIDENCE_FULL_CONTAINMENT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Containment {
+    pub sampled: u64,
+    pub matched: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct InferredRelation {
+    pub table_name: String,
+    pub column_name: String,
+    pub referenced_table_name: String,
+    pub referenced_column_name: String,
+    pub confidence: u32,
+    pub reasons: Vec<String>,
+    pub containment: Option<Containment>,
+    pub sample_error: Option<String>,
+}
+
+pub fn infer_relations(
+    columns: &[SchemaColumn],
+    foreign_keys: &[ForeignKey],
+) -> Vec<InferredRelation> {
+    let declared: HashSet<(&str, &str)> = foreign_keys.iter()
+        .flat_map(|fk| fk.column_names.iter()
+            .map(move |c| (fk.table_name.as_str(), c.as_str())))
+        .collect();
+
+    let mut table_columns: HashMap<String, Vec<&SchemaColumn>> = HashMap::new();
+    for col in columns {
+        table_columns.entry(col.table_name.to_lowercase()).or_default().push(col);
+    }
+
+    let mut relations: Vec<InferredRelation> = vec![];
+
+    for col in columns {
+        if declared.contains(&(col.table_name.as_str(), col.column_name.as_str())) {
+            continue;
+        }
+
+        let stem = match reference_stem(&col.column_name) {
+            Some(s) => s,
+            _ => continue,
+        };
+
+        for candidate in candidate_table_names(&stem) {
+            let parent_columns = match table_columns.get(&candidate) {
+                Some(c) => c,
+                _ => continue,
+            };
+            if parent_columns[0].table_name == col.table_name {
+                continue;
+            }
+
+            let parent = match referenced_column(parent_columns, &col.column_name) {
+                Some(p) => p,
+                _ => continue,
+            };
+
+            relations.push(score(col, parent));
+        }
+    }
+
+    sort_relations(&mut relations);
+    relations
+}
+
+pub fn sort_relations(relations: &mut [InferredRelation]) {
+    relations.sort_by(|a, b| b.confidence.cmp(&a.confidence)
+        .then_with(|| (&a.table_name, &a.column_name).cmp(&(&b.table_name, &b.column_name))));
+}
+
+pub fn apply_containment(relation: &mut InferredRelation, containment: Containment) {
+    if containment.sampled > 0 {
+        if containment.matched == containment.sampled {
+            relation.confidence = (relation.confidence + CONFIDENCE_FULL_CONTAINMENT).min(100);
+            relation.reasons.push(format!("all {} sampled values exist in the referenced column", containment.sampled));
+        } else {
+            relation.confidence = (relation.confidence as u64 * containment.matched / containment.sampled) as u32;
+            relation.reasons.push(format!("{} of {} sampled values exist in the referenced column",
+                containment.matched, containment.sampled));
+        }
+    }
+    relation.containment = Some(containment);
+}
+
+fn reference_stem(column_name: &str) -> Option<String> {
+    let lower = column_name.to_lowercase();
+    let stem = if lower.ends_with("_id") {
+        &column_name[..column_name.len() - 3]
+    } else if column_name.ends_with("Id") || column_name.ends_with("ID") {
+        &column_name[..column_name.len() - 2]
+    } else {
+        return None;
+    };
+
+    if stem.is_empty() {
+        None
+    } else {
+        Some(stem.to_lowercase())
+    }
+}
+
+fn candidate_table_names(stem: &str) -> Vec<String> {
+    let mut names = vec![
+        stem.to_string(),
+        format!("{}s", stem),
+        format!("{}es", stem),
+    ];
+    if let Some(s) = stem.strip_suffix('y') {
+        names.push(format!("{}ies", s));
+    }
+    names
+}
+
+fn referenced_column<'a>(
+    parent_columns: &[&'a SchemaColumn],
+    column_name: &str,
+) -> Option<&'a SchemaColumn> {
+    let primary_keys: Vec<&&SchemaColumn> = parent_columns.iter()
+        .filter(|c| c.column_key == "PRI")
+        .collect();
+
+    parent_columns.iter()
+        .find(|c| c.column_name.eq_ignore_ascii_case(column_name))
+        .or_else(|| parent_columns.iter().find(|c| c.column_name.eq_ignore_ascii_case("id")))
+        .or(if primary_keys.len() == 1 { Some(primary_keys[0]) } else { None })
+        .cloned()
+}
+
+fn score(col: &SchemaColumn, parent: &SchemaColumn) -> InferredRelation {
+    let mut confidence = CONFIDENCE_NAME_MATCH;
+    let mut reasons = vec![format!("`{}` names table `{}`", col.column_name, parent.table_name)];
+
+    if parent.column_key == "PRI" {
+        confidence += CONFIDENCE_PRIMARY_KEY;
+        reasons.push(format!("`{}` is the primary key", parent.column_name));
+    }
+
+    if col.column_type == parent.column_type {
+        confidence += CONFIDENCE_SAME_COLUMN_TYPE;
+        reasons.push(format!("both columns are `{}`", col.column_type));
+    } else if col.data_type == parent.data_type {
+        confidence += CONFIDENCE_SAME_DATA_TYPE;
+        reasons.push(format!("both columns are `{}`", col.data_type));
+    }
+
+    InferredRelation {
+        table_name: col.table_name.clone(),
+        column_name: col.column_name.clone(),
+        referenced_table_name: parent.table_name.clone(),
+        referenced_column_name: parent.column_name.clone(),
+        confidence,
+        reasons,
+        containment: None,
+        sample_error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(table_name: &str, column_name: &str, column_type: &str, column_key: &str) -> SchemaColumn {
+        SchemaColumn {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+            data_type: column_type.split('(').next().unwrap().to_string(),
+            column_type: column_type.to_string(),
+            column_key: column_key.to_string(),
+            nullable: false,
+            column_default: None,
+            extra: String::new(),
+            character_set_name: None,
+            collation_name: None,
+            generation_expression: None,
+            column_comment: String::new(),
+        }
+    }
+
+    fn shop() -> Vec<SchemaColumn> {
+        vec![
+            column("categories", "id", "int(11)", "PRI"),
+            column("customers", "id", "int(11)", "PRI"),
+            column("orders", "id", "int(11)", "PRI"),
+            column("orders", "customer_id", "int(11)", "MUL"),
+            column("orders", "category_id", "bigint(20)", ""),
+            column("orders", "orderId", "int(11)", ""),
+        ]
+    }
+
+    #[test]
+    fn stems_match_singular_and_plural_table_names() {
+        assert_eq!(reference_stem("customer_id"), Some(String::from("customer")));
+        assert_eq!(reference_stem("Customer_ID"), Some(String::from("customer")));
+        assert_eq!(reference_stem("customerId"), Some(String::from("customer")));
+        assert_eq!(reference_stem("_id"), None);
+        assert_eq!(reference_stem("name"), None);
+        assert_eq!(candidate_table_names("category"), vec!["category", "categorys", "categoryes", "categories"]);
+    }
+
+    #[test]
+    fn scores_name_key_and_type_matches() {
+        let relations = infer_relations(&shop(), &[]);
+
+        let customer = relations.iter().find(|r| r.column_name == "customer_id").unwrap();
+        assert_eq!(customer.referenced_table_name, "customers");
+        assert_eq!(customer.referenced_column_name, "id");
+        assert_eq!(customer.confidence, CONFIDENCE_NAME_MATCH + CONFIDENCE_PRIMARY_KEY + CONFIDENCE_SAME_COLUMN_TYPE);
+
+        let category = relations.iter().find(|r| r.column_name == "category_id").unwrap();
+        assert_eq!(category.referenced_table_name, "categories");
+        assert_eq!(category.confidence, CONFIDENCE_NAME_MATCH + CONFIDENCE_PRIMARY_KEY);
+
+        assert!(relations.iter().all(|r| r.column_name != "orderId"));
+        assert_eq!(relations[0].column_name, "customer_id");
+    }
+
+    #[test]
+    fn declared_foreign_keys_are_not_inferred_again() {
+        let fk = ForeignKey {
+            constraint_name: String::from("fk_orders_customer"),
+            table_name: String::from("orders"),
+            column_names: vec![String::from("customer_id")],
+            referenced_table_name: String::from("customers"),
+            referenced_column_names: vec![String::from("id")],
+        };
+
+        let relations = infer_relations(&shop(), &[fk]);
+
+        assert!(relations.iter().all(|r| r.column_name != "customer_id"));
+        assert!(relations.iter().any(|r| r.column_name == "category_id"));
+    }
+
+    #[test]
+    fn ties_are_ordered_by_table_and_column() {
+        let columns = vec![
+            column("users", "id", "int(11)", "PRI"),
+            column("posts", "user_id", "int(11)", ""),
+            column("comments", "user_id", "int(11)", ""),
+        ];
+
+        let relations = infer_relations(&columns, &[]);
+
+        assert_eq!(relations.iter().map(|r| r.table_name.as_str()).collect::<Vec<_>>(), vec!["comments", "posts"]);
+    }
+
+    #[test]
+    fn containment_raises_or_scales_confidence() {
+        let mut relations = infer_relations(&shop(), &[]);
+
+        apply_containment(&mut relations[0], Containment { sampled: 10, matched: 10 });
+        apply_containment(&mut relations[1], Containment { sampled: 10, matched: 5 });
+
+        assert_eq!(relations[0].confidence, 100);
+        assert_eq!(relations[1].confidence, (CONFIDENCE_NAME_MATCH + CONFIDENCE_PRIMARY_KEY) / 2);
+        assert_eq!(relations[1].reasons.last().unwrap(), "5 of 10 sampled values exist in the referenced column");
+    }
+}
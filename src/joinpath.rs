@@ -0,0 +1,296 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+
+use crate::sql::{
+    ForeignKey,
+    quote_identifier,
+};
+
+pub const JOIN_PATH_LIMIT: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum JoinDirection {
+    ToParent,
+    ToChild,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct JoinCondition {
+    pub column_name: String,
+    pub joined_column_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct JoinStep {
+    pub table_name: String,
+    pub joined_table_name: String,
+    pub constraint_name: String,
+    pub direction: JoinDirection,
+    pub conditions: Vec<JoinCondition>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct JoinPath {
+    pub table_names: Vec<String>,
+    pub steps: Vec<JoinStep>,
+    pub sql: String,
+}
+
+pub fn find_shortest_paths(
+    foreign_keys: &[ForeignKey],
+    from_table_name: &str,
+    to_table_name: &str,
+    limit: usize,
+) -> Vec<JoinPath> {
+    if from_table_name == to_table_name {
+        return vec![JoinPath {
+            table_names: vec![from_table_name.to_string()],
+            steps: vec![],
+            sql: join_sql(from_table_name, &[]),
+        }];
+    }
+
+    let edges = edges(foreign_keys);
+    let from_dist = distances(&edges, from_table_name);
+    let to_dist = distances(&edges, to_table_name);
+
+    let shortest = match from_dist.get(to_table_name) {
+        Some(d) => *d,
+        _ => return vec![],
+    };
+
+    let search = PathSearch {
+        edges: &edges,
+        from_dist: &from_dist,
+        to_dist: &to_dist,
+        shortest,
+        to_table_name,
+        limit,
+    };
+    let mut paths: Vec<Vec<JoinStep>> = vec![];
+    search.collect(from_table_name, &mut vec![], &mut paths);
+
+    paths.into_iter().map(|steps| {
+        let mut table_names = vec![from_table_name.to_string()];
+        table_names.extend(steps.iter().map(|s| s.joined_table_name.clone()));
+        JoinPath {
+            sql: join_sql(from_table_name, &steps),
+            table_names,
+            steps,
+        }
+    }).collect()
+}
+
+fn edges(foreign_keys: &[ForeignKey]) -> HashMap<&str, Vec<JoinStep>> {
+    let mut edges: HashMap<&str, Vec<JoinStep>> = HashMap::new();
+
+    for fk in foreign_keys.iter().filter(|fk| fk.table_name != fk.referenced_table_name) {
+        let to_parent = JoinStep {
+            table_name: fk.table_name.clone(),
+            joined_table_name: fk.referenced_table_name.clone(),
+            constraint_name: fk.constraint_name.clone(),
+            direction: JoinDirection::ToParent,
+            conditions: fk.column_names.iter().zip(fk.referenced_column_names.iter())
+                .map(|(c, p)| JoinCondition {
+                    column_name: c.clone(),
+                    joined_column_name: p.clone(),
+                }).collect(),
+        };
+        let to_child = JoinStep {
+            table_name: fk.referenced_table_name.clone(),
+            joined_table_name: fk.table_name.clone(),
+            constraint_name: fk.constraint_name.clone(),
+            direction: JoinDirection::ToChild,
+            conditions: fk.referenced_column_names.iter().zip(fk.column_names.iter())
+                .map(|(p, c)| JoinCondition {
+                    column_name: p.clone(),
+                    joined_column_name: c.clone(),
+                }).collect(),
+        };
+
+        edges.entry(fk.table_name.as_str()).or_default().push(to_parent);
+        edges.entry(fk.referenced_table_name.as_str()).or_default().push(to_child);
+    }
+
+    for steps in edges.values_mut() {
+        steps.sort_by(|a, b| (&a.joined_table_name, &a.constraint_name)
+            .cmp(&(&b.joined_table_name, &b.constraint_name)));
+    }
+
+    edges
+}
+
+fn distances<'a>(edges: &HashMap<&'a str, Vec<JoinStep>>, start: &'a str) -> HashMap<String, usize> {
+    let mut dist: HashMap<String, usize> = HashMap::new();
+    let mut queue: VecDeque<String> = VecDeque::new();
+
+    dist.insert(start.to_string(), 0);
+    queue.push_back(start.to_string());
+
+    while let Some(table_name) = queue.pop_front() {
+        let d = dist[&table_name];
+        if let Some(steps) = edges.get(table_name.as_str()) {
+            for step in steps {
+                if !dist.contains_key(&step.joined_table_name) {
+                    dist.insert(step.joined_table_name.clone(), d + 1);
+                    queue.push_back(step.joined_table_name.clone());
+                }
+            }
+        }
+    }
+
+    dist
+}
+
+struct PathSearch<'a> {
+    edges: &'a HashMap<&'a str, Vec<JoinStep>>,
+    from_dist: &'a HashMap<String, usize>,
+    to_dist: &'a HashMap<String, usize>,
+    shortest: usize,
+    to_table_name: &'a str,
+    limit: usize,
+}
+
+impl<'a> PathSearch<'a> {
+    fn collect(&self, table_name: &str, current: &mut Vec<JoinStep>, paths: &mut Vec<Vec<JoinStep>>) {
+        if paths.len() >= self.limit {
+            return;
+        }
+        if table_name == self.to_table_name {
+            paths.push(current.clone());
+            return;
+        }
+
+        let steps = match self.edges.get(table_name) {
+            Some(s) => s,
+            _ => return,
+        };
+
+        for step in steps {
+            let next = step.joined_table_name.as_str();
+            let on_shortest = match (self.from_dist.get(next), self.to_dist.get(next)) {
+                (Some(f), Some(t)) => *f == current.len() + 1 && f + t == self.shortest,
+                _ => false,
+            };
+            if !on_shortest {
+                continue;
+            }
+
+            current.push(step.clone());
+            self.collect(next, current, paths);
+            current.pop();
+        }
+    }
+}
+
+fn join_sql(from_table_name: &str, steps: &[JoinStep]) -> String {
+    let mut sql = format!("SELECT\n    *\nFROM\n    {}\n", quote_identifier(from_table_name));
+
+    for step in steps {
+        let joined = quote_identifier(&step.joined_table_name);
+        let table = quote_identifier(&step.table_name);
+        sql.push_str(&format!("INNER JOIN\n    {}\n", joined));
+        for (i, cond) in step.conditions.iter().enumerate() {
+            sql.push_str(&format!(
+                "    {} {}.{} = {}.{}\n",
+                if i == 0 { "ON " } else { "AND" },
+                joined, quote_identifier(&cond.joined_column_name),
+                table, quote_identifier(&cond.column_name),
+            ));
+        }
+    }
+
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fk(name: &str, table: &str, columns: &[&str], parent: &str, parent_columns: &[&str]) -> ForeignKey {
+        ForeignKey {
+            constraint_name: name.to_string(),
+            table_name: table.to_string(),
+            column_names: columns.iter().map(|c| c.to_string()).collect(),
+            referenced_table_name: parent.to_string(),
+            referenced_column_names: parent_columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn names(path: &JoinPath) -> Vec<&str> {
+        path.table_names.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn walks_foreign_keys_in_both_directions() {
+        let fks = vec![
+            fk("fk_orders_customer", "orders", &["customer_id"], "customers", &["id"]),
+            fk("fk_items_order", "order_items", &["order_id"], "orders", &["id"]),
+        ];
+
+        let paths = find_shortest_paths(&fks, "customers", "order_items", JOIN_PATH_LIMIT);
+
+        assert_eq!(paths.len(), 1);
+        assert_eq!(names(&paths[0]), vec!["customers", "orders", "order_items"]);
+        assert_eq!(paths[0].steps[0].direction, JoinDirection::ToChild);
+        assert_eq!(paths[0].steps[1].direction, JoinDirection::ToChild);
+        assert_eq!(find_shortest_paths(&fks, "order_items", "customers", JOIN_PATH_LIMIT)[0].steps[0].direction,
+            JoinDirection::ToParent);
+    }
+
+    #[test]
+    fn returns_every_path_of_equal_length() {
+        let fks = vec![
+            fk("fk_orders_billing", "orders", &["billing_address_id"], "addresses", &["id"]),
+            fk("fk_orders_shipping", "orders", &["shipping_address_id"], "addresses", &["id"]),
+            fk("fk_orders_customer", "orders", &["customer_id"], "customers", &["id"]),
+            fk("fk_addresses_customer", "addresses", &["customer_id"], "customers", &["id"]),
+        ];
+
+        let paths = find_shortest_paths(&fks, "orders", "addresses", JOIN_PATH_LIMIT);
+
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].steps[0].constraint_name, "fk_orders_billing");
+        assert_eq!(paths[1].steps[0].constraint_name, "fk_orders_shipping");
+        assert_eq!(find_shortest_paths(&fks, "orders", "addresses", 1).len(), 1);
+    }
+
+    #[test]
+    fn cycles_and_self_references_terminate() {
+        let fks = vec![
+            fk("fk_a_b", "a", &["b_id"], "b", &["id"]),
+            fk("fk_b_c", "b", &["c_id"], "c", &["id"]),
+            fk("fk_c_a", "c", &["a_id"], "a", &["id"]),
+            fk("fk_a_parent", "a", &["parent_id"], "a", &["id"]),
+        ];
+
+        let paths = find_shortest_paths(&fks, "a", "c", JOIN_PATH_LIMIT);
+
+        assert_eq!(paths.len(), 1);
+        assert_eq!(names(&paths[0]), vec!["a", "c"]);
+        assert_eq!(names(&find_shortest_paths(&fks, "a", "a", JOIN_PATH_LIMIT)[0]), vec!["a"]);
+    }
+
+    #[test]
+    fn unconnected_tables_have_no_path() {
+        let fks = vec![fk("fk_orders_customer", "orders", &["customer_id"], "customers", &["id"])];
+
+        assert!(find_shortest_paths(&fks, "orders", "products", JOIN_PATH_LIMIT).is_empty());
+    }
+
+    #[test]
+    fn sql_joins_every_column_and_quotes_identifiers() {
+        let fks = vec![fk("fk_lines", "order`lines", &["order_id", "shop id"], "orders", &["id", "shop_id"])];
+
+        let paths = find_shortest_paths(&fks, "orders", "order`lines", JOIN_PATH_LIMIT);
+
+        assert_eq!(paths[0].sql, concat!(
+            "SELECT\n    *\nFROM\n    `orders`\n",
+            "INNER JOIN\n    `order``lines`\n",
+            "    ON  `order``lines`.`order_id` = `orders`.`id`\n",
+            "    AND `order``lines`.`shop id` = `orders`.`shop_id`\n",
+        ));
+    }
+}
@@ -3,8 +3,29 @@ extern crate mysql;
 #[macro_use]
 extern crate serde_derive;
 
+pub mod autocomplete;
+pub mod cli;
+pub mod cardinality;
+pub mod catalog;
+pub mod conditional;
 pub mod datasource;
-pub mod envvar;
+pub mod diff;
+pub mod ddl;
+pub mod diagram;
+pub mod domain;
+pub mod error;
 pub mod handler;
+pub mod inference;
+pub mod joinpath;
+pub mod lint;
+pub mod page;
+pub mod partition;
+pub mod rowcount;
+pub mod search;
+pub mod settings;
+pub mod site;
+pub mod snapshot;
 pub mod sql;
+pub mod view;
+pub mod watchdog;
 
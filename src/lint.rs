@@ -0,0 +1,211 @@
+use crate::sql::{
+    ForeignKey,
+    Index,
+    IndexColumn,
+    quote_identifier,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum FindingKind {
+    UnindexedForeignKey,
+    DuplicateIndex,
+    RedundantIndex,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub table_name: String,
+    pub message: String,
+    pub ddl: String,
+}
+
+pub fn lint(foreign_keys: &[ForeignKey], indexes: &[Index]) -> Vec<Finding> {
+    let mut findings = unindexed_foreign_keys(foreign_keys, indexes);
+    findings.extend(redundant_indexes(indexes));
+    findings.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+    findings
+}
+
+pub fn unindexed_foreign_keys(foreign_keys: &[ForeignKey], indexes: &[Index]) -> Vec<Finding> {
+    foreign_keys.iter()
+        .filter(|fk| !indexes.iter()
+            .filter(|i| i.table_name == fk.table_name)
+            .any(|i| i.columns.len() >= fk.column_names.len()
+                && fk.column_names.iter().zip(i.columns.iter())
+                    .all(|(c, ic)| ic.column_name.as_ref() == Some(c))))
+        .map(|fk| Finding {
+            kind: FindingKind::UnindexedForeignKey,
+            table_name: fk.table_name.clone(),
+            message: format!(
+                "foreign key {} ({}) has no index with these columns as a leading prefix",
+                fk.constraint_name, fk.column_names.join(", "),
+            ),
+            ddl: format!(
+                "ALTER TABLE {} ADD INDEX {} ({});",
+                quote_identifier(&fk.table_name),
+                quote_identifier(&format!("idx_{}_{}", fk.table_name, fk.column_names.join("_"))),
+                fk.column_names.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+            ),
+        })
+        .collect()
+}
+
+pub fn redundant_indexes(indexes: &[Index]) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = vec![];
+
+    for (i, index) in indexes.iter().enumerate() {
+        if index.primary || !is_btree(index) {
+            continue;
+        }
+
+        let covering = indexes.iter().enumerate()
+            .filter(|(j, other)| *j != i && other.table_name == index.table_name && is_btree(other))
+            .find(|(j, other)| {
+                if same_columns(&index.columns, &other.columns) {
+                    // Of two identical indexes keep the one enforcing a constraint, otherwise the first.
+                    other.primary || (other.unique && !index.unique) || (other.unique == index.unique && j < &i)
+                } else {
+                    !index.unique && is_left_prefix(&index.columns, &other.columns)
+                }
+            });
+
+        if let Some((_, other)) = covering {
+            let duplicate = same_columns(&index.columns, &other.columns);
+            findings.push(Finding {
+                kind: if duplicate { FindingKind::DuplicateIndex } else { FindingKind::RedundantIndex },
+                table_name: index.table_name.clone(),
+                message: format!(
+                    "index {} ({}) is {} index {} ({})",
+                    index.index_name, column_list(&index.columns),
+                    if duplicate { "a duplicate of" } else { "a left prefix of" },
+                    other.index_name, column_list(&other.columns),
+                ),
+                ddl: format!(
+                    "ALTER TABLE {} DROP INDEX {};",
+                    quote_identifier(&index.table_name),
+                    quote_identifier(&index.index_name),
+                ),
+            });
+        }
+    }
+
+    findings
+}
+
+fn is_btree(index: &Index) -> bool {
+    index.index_type == "BTREE"
+}
+
+fn same_columns(a: &[IndexColumn], b: &[IndexColumn]) -> bool {
+    a.len() == b.len() && is_left_prefix(a, b)
+}
+
+fn is_left_prefix(prefix: &[IndexColumn], columns: &[IndexColumn]) -> bool {
+    prefix.len() <= columns.len()
+        && prefix.iter().zip(columns.iter())
+            .all(|(p, c)| p.column_name.is_some() && p.column_name == c.column_name && p.sub_part == c.sub_part)
+}
+
+fn column_list(columns: &[IndexColumn]) -> String {
+    columns.iter()
+        .map(|c| c.column_name.clone().unwrap_or_else(|| String::from("(expression)")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(table: &str, name: &str, unique: bool, columns: &[&str]) -> Index {
+        Index {
+            table_name: table.to_string(),
+            index_name: name.to_string(),
+            primary: name == "PRIMARY",
+            unique: unique || name == "PRIMARY",
+            index_type: String::from("BTREE"),
+            visible: true,
+            cardinality: None,
+            columns: columns.iter().map(|c| IndexColumn {
+                column_name: Some(c.to_string()),
+                sub_part: None,
+                cardinality: None,
+            }).collect(),
+        }
+    }
+
+    fn fk(table: &str, columns: &[&str]) -> ForeignKey {
+        ForeignKey {
+            constraint_name: format!("fk_{}", table),
+            table_name: table.to_string(),
+            column_names: columns.iter().map(|c| c.to_string()).collect(),
+            referenced_table_name: String::from("shops"),
+            referenced_column_names: columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn kinds(findings: &[Finding]) -> Vec<(&str, FindingKind)> {
+        findings.iter().map(|f| (f.table_name.as_str(), f.kind.clone())).collect()
+    }
+
+    #[test]
+    fn foreign_key_needs_an_index_with_its_columns_as_prefix() {
+        let fks = vec![fk("orders", &["shop_id", "customer_id"]), fk("carts", &["shop_id", "customer_id"])];
+        let indexes = vec![
+            index("orders", "idx_shop_customer_date", false, &["shop_id", "customer_id", "ordered_at"]),
+            index("carts", "idx_customer_shop", false, &["customer_id", "shop_id"]),
+        ];
+
+        let findings = unindexed_foreign_keys(&fks, &indexes);
+
+        assert_eq!(kinds(&findings), vec![("carts", FindingKind::UnindexedForeignKey)]);
+        assert_eq!(findings[0].ddl, "ALTER TABLE `carts` ADD INDEX `idx_carts_shop_id_customer_id` (`shop_id`, `customer_id`);");
+    }
+
+    #[test]
+    fn left_prefix_of_another_index_is_redundant() {
+        let indexes = vec![
+            index("orders", "idx_shop", false, &["shop_id"]),
+            index("orders", "idx_shop_customer", false, &["shop_id", "customer_id"]),
+            index("orders", "uq_shop", true, &["customer_id"]),
+            index("orders", "idx_customer_shop", false, &["customer_id", "shop_id"]),
+        ];
+
+        let findings = redundant_indexes(&indexes);
+
+        assert_eq!(kinds(&findings), vec![("orders", FindingKind::RedundantIndex)]);
+        assert!(findings[0].message.starts_with("index idx_shop (shop_id) is a left prefix of index idx_shop_customer"));
+        assert_eq!(findings[0].ddl, "ALTER TABLE `orders` DROP INDEX `idx_shop`;");
+    }
+
+    #[test]
+    fn of_identical_indexes_the_constraint_is_kept() {
+        let indexes = vec![
+            index("orders", "idx_id", false, &["id"]),
+            index("orders", "PRIMARY", true, &["id"]),
+            index("orders", "idx_code_a", false, &["code"]),
+            index("orders", "idx_code_b", false, &["code"]),
+        ];
+
+        let findings = redundant_indexes(&indexes);
+        let dropped: Vec<&str> = findings.iter().map(|f| f.ddl.as_str()).collect();
+
+        assert_eq!(dropped, vec![
+            "ALTER TABLE `orders` DROP INDEX `idx_id`;",
+            "ALTER TABLE `orders` DROP INDEX `idx_code_b`;",
+        ]);
+        assert!(findings.iter().all(|f| f.kind == FindingKind::DuplicateIndex));
+    }
+
+    #[test]
+    fn prefix_lengths_and_index_types_must_match() {
+        let mut prefixed = index("users", "idx_email_10", false, &["email"]);
+        prefixed.columns[0].sub_part = Some(10);
+        let mut fulltext = index("users", "ft_email", false, &["email"]);
+        fulltext.index_type = String::from("FULLTEXT");
+        let indexes = vec![prefixed, index("users", "idx_email", false, &["email"]), fulltext];
+
+        assert!(redundant_indexes(&indexes).is_empty());
+    }
+}
@@ -1,50 +1,131 @@
 extern crate rustack;
-#[macro_use]
 extern crate tera;
 
 use actix_web::{
     App,
     HttpServer,
     middleware,
+    web,
 };
 
+use clap::ArgMatches;
+
 use rustack::{
     datasource,
-    envvar,
+    catalog,
+    cli,
     handler,
+    rowcount,
+};
+use rustack::conditional::PageVersions;
+use rustack::error::AppError;
+use rustack::settings::{
+    Features,
+    Settings,
 };
 
 fn main() {
-    std::env::set_var("RUST_LOG", "actix_web=info");
-    env_logger::init();
+    let matches = match cli::app().get_matches_safe() {
+        Ok(m) => m,
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            std::process::exit(cli::EXIT_USAGE);
+        },
+        Err(e) => e.exit(),
+    };
+    let env = |name: &str| std::env::var(name).ok();
+
+    let result = match matches.subcommand() {
+        ("serve", Some(m)) => serve(m, &env),
+        _ => cli::run(&matches, &env),
+    };
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+fn serve(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Result<i32, AppError> {
+    let settings = Settings::from_matches(matches, env)?;
+
+    env_logger::Builder::new()
+        .parse_filters(&settings.server.log_level)
+        .init();
 
-    HttpServer::new(|| {
-        let host = envvar::load::<String>(datasource::DATABASE_HOST, Some(String::from(datasource::DATABASE_HOST_DEFAULT)));
-        let port = envvar::load::<u32>(datasource::DATABASE_PORT, Some(datasource::DATABASE_PORT_DEFAULT));
-        let username = envvar::load::<String>(datasource::DATABASE_USERNAME, None);
-        let password = envvar::load::<String>(datasource::DATABASE_PASSWORD, None);
-        let name = envvar::load::<String>(datasource::DATABASE_NAME, None);
-        let pool_size = envvar::load::<u32>(datasource::DATABASE_POOL_SIZE, Some(datasource::DATABASE_POOL_SIZE_DEFAULT));
+    let datasource = datasource::DataSource::new(&settings.datasource)?;
 
-        let datasource = datasource::DataSource::new(
-            host, port, username, password, name, pool_size
-        );
+    let tera = handler::templates()?;
 
-        let tera = compile_templates!(
-            concat!(env!("CARGO_MANIFEST_DIR"),
-            "/templates/**/*"));
+    let catalog = catalog::CatalogCache::new(
+        settings.catalog.check_interval_secs,
+        settings.catalog.max_age_secs,
+    );
+    let row_counts = rowcount::RowCountCache::new();
 
+    // Load the catalog in the background so /readyz can turn ready without traffic.
+    catalog.start(&datasource.conn_pool, &datasource.name);
+
+    let features = settings.features;
+    let config = web::Data::new(handler::AppConfig {
+        datasource,
+        tera,
+        catalog,
+        row_counts,
+        page_versions: PageVersions::new(),
+        row_count_timeout_ms: settings.row_counts.timeout_ms,
+        features,
+    });
+
+    let address = (settings.server.bind_address.as_str(), settings.server.port);
+    let server = HttpServer::new(move || {
         App::new()
-            .data(handler::AppConfig {
-                datasource,
-                tera,
-            })
+            .register_data(config.clone())
             .wrap(middleware::Logger::default())
-            .service(handler::get_tables)
-            .service(handler::get_table_by_name)
-    })
-    .bind("127.0.0.1:8088")
-    .unwrap()
-    .run()
-    .unwrap();
+            .configure(|cfg| routes(cfg, features))
+    });
+    server.bind(address)
+        .and_then(|server| server.run())
+        .map_err(|e| AppError::Io(format!("could not listen on {}:{}: {}", address.0, address.1, e)))?;
+    Ok(cli::EXIT_OK)
+}
+
+fn routes(cfg: &mut web::ServiceConfig, features: Features) {
+    cfg.service(handler::get_healthz)
+        .service(handler::get_readyz)
+        .service(handler::get_tables)
+        .service(handler::get_table_by_name)
+        .service(handler::get_table_ddl)
+        .service(handler::get_schema_ddl)
+        .service(handler::get_joins)
+        .service(handler::get_joins_json)
+        .service(handler::get_search)
+        .service(handler::get_search_json)
+        .service(handler::get_autocomplete_json)
+        .service(handler::get_diagram)
+        .service(handler::get_diagram_mermaid)
+        .service(handler::get_schema_health)
+        .service(handler::get_schema_health_json)
+        .service(handler::get_views)
+        .service(handler::get_view_by_name)
+        .service(handler::get_routines)
+        .service(handler::get_routine_by_name)
+        .service(handler::get_triggers)
+        .service(handler::get_events);
+
+    if features.exact_row_counts {
+        cfg.service(handler::post_row_counts_refresh)
+            .service(handler::get_row_counts_json);
+    }
+    if features.inferred_relations {
+        cfg.service(handler::get_inferred_relations)
+            .service(handler::get_inferred_relations_json);
+    }
+    if features.admin {
+        cfg.service(handler::get_catalog_status_json)
+            .service(handler::get_pool_stats_json)
+            .service(handler::post_catalog_refresh);
+    }
 }
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use crate::{
+    cardinality,
+    view,
+};
+use crate::catalog::Catalog;
+use crate::inference::InferredRelation;
+use crate::rowcount::{
+    ExactRowCount,
+    RowCountCache,
+};
+use crate::settings::Features;
+use crate::sql::{
+    ForeignKey,
+    SchemaColumn,
+    Table,
+    TableSizeResult,
+    UniqueKey,
+    View,
+};
+use crate::view::ViewDependencies;
+
+// Template contexts shared by the handlers and the static site export, so both render
+// the same pages from the same data.
+
+// A name as one URL path segment and file name. Only ASCII letters, digits, `-` and `_`
+// are kept, so `/`, `..`, `?`, `#` and spaces in an identifier can neither leave the
+// segment nor the export directory. Matches encodeURIComponent plus `.!~*'()`.
+pub fn path_segment(name: &str) -> String {
+    name.bytes()
+        .map(|b| if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            (b as char).to_string()
+        } else {
+            format!("%{:02X}", b)
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct TableOutline {
+    fqn: String,
+    name: String,
+    column_count: u32,
+    row_count: u64,
+    exact_row_count: Option<ExactRowCount>,
+    row_count_deviation: Option<i64>,
+    engine: Option<String>,
+    row_format: Option<String>,
+    data_length: u64,
+    index_length: u64,
+    data_free: u64,
+    auto_increment: Option<u64>,
+    table_collation: Option<String>,
+    create_options: Option<String>,
+    create_time: Option<String>,
+    update_time: Option<String>,
+    parent_count: u32,
+    child_count: u32,
+    inferred_parent_count: usize,
+    inferred_child_count: usize,
+    comment: String,
+    note: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ViewOutline {
+    pub view: View,
+    pub dependencies: ViewDependencies,
+}
+
+pub fn tables(
+    db_name: &str,
+    catalog: &Catalog,
+    row_counts: &RowCountCache,
+    features: Features,
+) -> tera::Context {
+    let size_map: &HashMap<String, TableSizeResult> = &catalog.table_sizes;
+    let ref_parent_map: &HashMap<String, u32> = &catalog.parent_counts;
+    let ref_children_map: &HashMap<String, u32> = &catalog.child_counts;
+    let inferred: &[InferredRelation] = &catalog.inferred_relations;
+
+    let tables: Vec<TableOutline> = catalog.tables.iter().cloned().map(|t| {
+        let table_name: &str = t.table_name.as_str();
+        let size = size_map[table_name].clone();
+        let exact = row_counts.get(table_name);
+        TableOutline {
+            fqn: format!("{}.{}", db_name, table_name),
+            name: table_name.to_string(),
+            column_count: size.columns,
+            row_count: size.rows,
+            row_count_deviation: exact.as_ref().and_then(|e| e.deviation_percent(size.rows)),
+            exact_row_count: exact,
+            engine: size.engine,
+            row_format: size.row_format,
+            data_length: size.data_length,
+            index_length: size.index_length,
+            data_free: size.data_free,
+            auto_increment: size.auto_increment,
+            table_collation: size.table_collation,
+            create_options: size.create_options,
+            create_time: size.create_time,
+            update_time: size.update_time,
+            parent_count: if ref_parent_map.contains_key(table_name) { ref_parent_map[table_name] } else { 0 },
+            child_count: if ref_children_map.contains_key(table_name) { ref_children_map[table_name] } else { 0 },
+            inferred_parent_count: inferred.iter().filter(|r| r.table_name == table_name).count(),
+            inferred_child_count: inferred.iter().filter(|r| r.referenced_table_name == table_name).count(),
+            comment: t.table_comment.unwrap_or(String::from("")),
+            note: String::from("NOTEnote")
+        }
+    }).collect();
+
+    let mut ctx = tera::Context::new();
+    ctx.insert("tables", &tables);
+    ctx.insert("row_counts_refreshing", &row_counts.is_refreshing());
+    ctx.insert("catalog_loaded_at", &catalog.loaded_at);
+    ctx.insert("features", &features);
+    ctx
+}
+
+pub fn table(
+    db_name: &str,
+    catalog: &Catalog,
+    views: &[View],
+    mut table: Table,
+) -> tera::Context {
+    let table_name = &table.table_name.clone();
+
+    let inferred: &[InferredRelation] = &catalog.inferred_relations;
+    let foreign_keys: &[ForeignKey] = &catalog.foreign_keys;
+    let unique_keys: &[UniqueKey] = &catalog.unique_keys;
+    let columns: &[SchemaColumn] = &catalog.columns;
+
+    table.table_views = view::views_reading(db_name, views, table_name).into_iter()
+        .map(|v| v.view_name.clone())
+        .collect();
+
+    table.table_junction = cardinality::junction_tables(foreign_keys, unique_keys)
+        .into_iter()
+        .find(|j| &j.table_name == table_name);
+
+    for column in table.table_columns.iter_mut() {
+        let column_name = column.column_name.clone();
+        if let Some(parent) = column.column_parent.as_mut() {
+            parent.cardinality = foreign_keys.iter()
+                .find(|fk| &fk.table_name == table_name
+                    && fk.referenced_table_name == parent.table_name
+                    && fk.column_names.contains(&column_name))
+                .map(|fk| cardinality::of_foreign_key(fk, unique_keys, columns));
+        }
+        for child in column.column_children.iter_mut() {
+            child.cardinality = foreign_keys.iter()
+                .find(|fk| fk.table_name == child.table_name
+                    && &fk.referenced_table_name == table_name
+                    && fk.column_names.contains(&child.column_name))
+                .map(|fk| cardinality::of_foreign_key(fk, unique_keys, columns));
+        }
+
+        column.column_inferred_parents = inferred.iter()
+            .filter(|r| &r.table_name == table_name && r.column_name == column.column_name)
+            .cloned()
+            .collect();
+        column.column_inferred_children = inferred.iter()
+            .filter(|r| &r.referenced_table_name == table_name && r.referenced_column_name == column.column_name)
+            .cloned()
+            .collect();
+    }
+
+    let mut ctx = tera::Context::new();
+    ctx.insert("table", &table);
+    ctx
+}
+
+pub fn view_outlines(db_name: &str, views: Vec<View>) -> Vec<ViewOutline> {
+    views.into_iter().map(|v| ViewOutline {
+        dependencies: view::dependencies(db_name, &v.view_definition),
+        view: v,
+    }).collect()
+}
+
+pub fn view(outline: &ViewOutline, view_names: &[String], ddl: &Option<String>) -> tera::Context {
+    let mut ctx = tera::Context::new();
+    ctx.insert("view", &outline.view);
+    ctx.insert("reads", &view::reads(&outline.dependencies, view_names));
+    ctx.insert("ddl", ddl);
+    ctx
+}
@@ -0,0 +1,187 @@
+use crate::sql::{
+    Partition,
+    Partitioning,
+};
+
+const MAXVALUE: &str = "MAXVALUE";
+
+// Warn once less than this share of the last range partition is left.
+pub const HEADROOM_WARNING_PERCENT: f64 = 20.0;
+
+pub fn last_bounded_range(partitioning: &Partitioning) -> Option<&Partition> {
+    if !partitioning.method.starts_with("RANGE") {
+        return None;
+    }
+
+    partitioning.partitions.last()
+        .filter(|p| match &p.partition_description {
+            Some(d) => !d.contains(MAXVALUE),
+            _ => false,
+        })
+}
+
+// The expression whose MAX() in the last bounded partition is compared with its bound.
+// RANGE COLUMNS over several columns bounds tuples, which a single MAX() cannot compare.
+pub fn max_expression(partitioning: &Partitioning) -> Option<&str> {
+    last_bounded_range(partitioning)?;
+    let expression = partitioning.expression.as_deref()?;
+    if partitioning.method == "RANGE COLUMNS" && expression.contains(',') {
+        return None;
+    }
+    Some(expression)
+}
+
+pub fn exhaustion_warning(partitioning: &Partitioning) -> Option<String> {
+    let last = last_bounded_range(partitioning)?;
+    let headroom = headroom_percent(partitioning)?;
+    if headroom >= HEADROOM_WARNING_PERCENT {
+        return None;
+    }
+
+    Some(format!(
+        "Only {:.0}% of the last range partition {} is left (current max {}, bound {}). Add partitions before inserts beyond the bound fail.",
+        headroom,
+        last.partition_name,
+        partitioning.current_max.as_ref()?,
+        unquote(last.partition_description.as_ref()?),
+    ))
+}
+
+// How much of the last bounded partition's range is still above the current max, measured
+// against the width of the range from the previous partition's bound. None when the bounds
+// are neither numbers nor dates, or there is no previous partition to measure from.
+pub fn headroom_percent(partitioning: &Partitioning) -> Option<f64> {
+    let last = last_bounded_range(partitioning)?;
+    let count = partitioning.partitions.len();
+    if count < 2 {
+        return None;
+    }
+
+    let upper = bound_value(last.partition_description.as_ref()?)?;
+    let lower = bound_value(partitioning.partitions[count - 2].partition_description.as_ref()?)?;
+    let max = bound_value(partitioning.current_max.as_ref()?)?;
+    if upper <= lower {
+        return None;
+    }
+
+    Some(((upper - max) / (upper - lower) * 100.0).clamp(0.0, 100.0))
+}
+
+// A partition bound or MAX() value as a number: plain numbers as they are, and dates
+// and datetimes, as RANGE COLUMNS bounds them, as days since 1970-01-01.
+fn bound_value(value: &str) -> Option<f64> {
+    let value = unquote(value);
+    if let Ok(n) = value.parse::<f64>() {
+        return Some(n);
+    }
+
+    let (date, time) = match value.split_once(' ') {
+        Some((d, t)) => (d, Some(t)),
+        None => (value.as_str(), None),
+    };
+    let mut parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let seconds = match time {
+        Some(t) => t.split(':')
+            .map(|p| p.parse::<f64>().ok())
+            .try_fold((0.0, 3600.0), |(total, unit), p| Some((total + p? * unit, unit / 60.0)))?
+            .0,
+        None => 0.0,
+    };
+    Some(days_from_civil(year, month, day) as f64 + seconds / 86400.0)
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn unquote(description: &str) -> String {
+    description.trim().trim_matches('\'').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partition(name: &str, description: Option<&str>) -> Partition {
+        Partition {
+            partition_name: name.to_string(),
+            partition_description: description.map(String::from),
+            table_rows: 0,
+            data_length: 0,
+            index_length: 0,
+        }
+    }
+
+    fn range(method: &str, expression: &str, bounds: &[&str], current_max: Option<&str>) -> Partitioning {
+        Partitioning {
+            method: method.to_string(),
+            expression: Some(expression.to_string()),
+            subpartition_method: None,
+            subpartition_expression: None,
+            partitions: bounds.iter().enumerate()
+                .map(|(i, b)| partition(&format!("p{}", i), Some(b)))
+                .collect(),
+            current_max: current_max.map(String::from),
+            max_error: None,
+            warning: None,
+        }
+    }
+
+    #[test]
+    fn bounds_parse_as_numbers_and_dates() {
+        assert_eq!(bound_value("100"), Some(100.0));
+        assert_eq!(bound_value(" -2.5 "), Some(-2.5));
+        assert_eq!(bound_value("'1970-01-01'"), Some(0.0));
+        assert_eq!(bound_value("'2000-03-01'"), Some(11_017.0));
+        assert_eq!(bound_value("'1970-01-02 12:00:00'"), Some(1.5));
+        assert_eq!(bound_value("'abc'"), None);
+        assert_eq!(bound_value("'2024-13-01'"), None);
+        assert_eq!(bound_value("'2024-01'"), None);
+    }
+
+    #[test]
+    fn headroom_is_measured_from_the_previous_bound() {
+        let partitioning = range("RANGE", "id", &["100", "200"], Some("190"));
+
+        assert_eq!(headroom_percent(&partitioning).map(|h| h.round()), Some(10.0));
+        assert_eq!(headroom_percent(&range("RANGE", "id", &["200"], Some("190"))), None);
+        assert_eq!(headroom_percent(&range("RANGE", "id", &["100", "200"], None)), None);
+        assert_eq!(headroom_percent(&range("RANGE", "id", &["100", "200", "MAXVALUE"], Some("190"))), None);
+        assert_eq!(headroom_percent(&range("LIST", "id", &["100", "200"], Some("190"))), None);
+    }
+
+    #[test]
+    fn warns_only_below_the_threshold() {
+        assert_eq!(exhaustion_warning(&range("RANGE", "id", &["100", "200"], Some("150"))), None);
+        assert_eq!(exhaustion_warning(&range("RANGE", "id", &["100", "200"], Some("110"))), None);
+
+        let warning = exhaustion_warning(&range("RANGE", "id", &["100", "200"], Some("185"))).unwrap();
+        assert!(warning.starts_with("Only 15% of the last range partition p1 is left (current max 185, bound 200)"));
+    }
+
+    #[test]
+    fn dates_warn_near_the_bound() {
+        let bounds = ["'2024-01-01'", "'2024-02-01'"];
+
+        assert_eq!(exhaustion_warning(&range("RANGE COLUMNS", "`created`", &bounds, Some("2024-01-10"))), None);
+        assert!(exhaustion_warning(&range("RANGE COLUMNS", "`created`", &bounds, Some("2024-01-30 08:00:00"))).is_some());
+    }
+
+    #[test]
+    fn multi_column_range_columns_has_no_max_expression() {
+        assert_eq!(max_expression(&range("RANGE", "YEAR(created)", &["2024", "2025"], None)), Some("YEAR(created)"));
+        assert_eq!(max_expression(&range("RANGE COLUMNS", "`a`", &["1", "2"], None)), Some("`a`"));
+        assert_eq!(max_expression(&range("RANGE COLUMNS", "`a`,`b`", &["1,1", "2,2"], None)), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    RwLock,
+    atomic::{
+        AtomicBool,
+        AtomicU64,
+        Ordering,
+    },
+};
+use std::thread;
+use std::time::{
+    Instant,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use actix_web::http::header::HttpDate;
+use r2d2_mysql::MysqlConnectionManager;
+
+use crate::sql;
+
+pub const ROW_COUNT_TIMEOUT_MS: &str = "ROW_COUNT_TIMEOUT_MS";
+pub const ROW_COUNT_TIMEOUT_MS_DEFAULT: u64 = 5000;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ExactRowCount {
+    pub count: Option<u64>,
+    pub timed_out: bool,
+    pub error: Option<String>,
+    pub taken_at: String,
+    pub taken_at_epoch: u64,
+    pub elapsed_ms: u64,
+}
+
+impl ExactRowCount {
+    pub fn deviation_percent(&self, estimate: u64) -> Option<i64> {
+        match self.count {
+            Some(0) => None,
+            Some(c) => Some(((estimate as f64 - c as f64) / c as f64 * 100.0).round() as i64),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RowCountCache {
+    counts: Arc<RwLock<HashMap<String, ExactRowCount>>>,
+    refreshing: Arc<AtomicBool>,
+    changes: Arc<AtomicU64>,
+    modified_epoch: Arc<AtomicU64>,
+}
+
+impl RowCountCache {
+    pub fn new() -> RowCountCache {
+        RowCountCache::default()
+    }
+
+    pub fn get(&self, table_name: &str) -> Option<ExactRowCount> {
+        self.counts.read().unwrap().get(table_name).cloned()
+    }
+
+    pub fn all(&self) -> HashMap<String, ExactRowCount> {
+        self.counts.read().unwrap().clone()
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.refreshing.load(Ordering::SeqCst)
+    }
+
+    // Counts every change to the cached counts or the refreshing flag, for validators.
+    pub fn changes(&self) -> u64 {
+        self.changes.load(Ordering::SeqCst)
+    }
+
+    pub fn modified_epoch(&self) -> u64 {
+        self.modified_epoch.load(Ordering::SeqCst)
+    }
+
+    fn touch(&self) {
+        self.changes.fetch_add(1, Ordering::SeqCst);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.modified_epoch.store(now, Ordering::SeqCst);
+    }
+
+    // Starts counting every table on a background thread. Returns false when a refresh
+    // is already running.
+    pub fn refresh(
+        &self,
+        pool: Arc<r2d2::Pool<MysqlConnectionManager>>,
+        db_name: String,
+        table_names: Vec<String>,
+        timeout_ms: u64,
+    ) -> bool {
+        if self.refreshing.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        self.touch();
+
+        let guard = Refreshing(self.clone());
+        thread::spawn(move || {
+            let cache = &guard.0;
+            for table_name in table_names {
+                let count = count_table(&pool, &db_name, &table_name, timeout_ms);
+                cache.counts.write().unwrap().insert(table_name, count);
+                cache.touch();
+            }
+        });
+
+        true
+    }
+}
+
+// Clears the refreshing flag when the refresh ends, also when it panics, so a failed
+// refresh does not block every later one.
+struct Refreshing(RowCountCache);
+
+impl Drop for Refreshing {
+    fn drop(&mut self) {
+        self.0.refreshing.store(false, Ordering::SeqCst);
+        self.0.touch();
+    }
+}
+
+fn count_table(
+    pool: &r2d2::Pool<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+    timeout_ms: u64,
+) -> ExactRowCount {
+    let now = SystemTime::now();
+    let started = Instant::now();
+
+    let result = match pool.get() {
+        Ok(mut conn) => sql::query_exact_row_count(&mut conn, db_name, table_name, timeout_ms),
+        Err(e) => Err(mysql::Error::from(std::io::Error::other(e.to_string()))),
+    };
+
+    let (count, timed_out, error) = match result {
+        Ok(c) => (Some(c), false, None),
+        Err(ref e) if sql::is_timeout(e) => (None, true, None),
+        Err(e) => (None, false, Some(e.to_string())),
+    };
+
+    ExactRowCount {
+        count,
+        timed_out,
+        error,
+        taken_at: HttpDate::from(now).to_string(),
+        taken_at_epoch: now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refreshing_flag_is_cleared_when_the_refresh_panics() {
+        let cache = RowCountCache::new();
+        cache.refreshing.store(true, Ordering::SeqCst);
+        let changes = cache.changes();
+
+        let guard = Refreshing(cache.clone());
+        let result = thread::spawn(move || {
+            let _guard = guard;
+            panic!("count failed");
+        }).join();
+
+        assert!(result.is_err());
+        assert!(!cache.is_refreshing());
+        assert!(cache.changes() > changes);
+    }
+}
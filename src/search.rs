@@ -0,0 +1,453 @@
+use crate::sql::{
+    SchemaColumn,
+    TableOutlineResult,
+};
+
+pub const SEARCH_RESULT_LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Table,
+    Column,
+    Type,
+    Comment,
+    Note,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HitKind {
+    Table,
+    Column,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Document {
+    pub kind: HitKind,
+    pub table_name: String,
+    pub column_name: Option<String>,
+    pub column_type: Option<String>,
+    pub comment: String,
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Term {
+    pub field: Option<Field>,
+    pub text: String,
+    pub prefix: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Segment {
+    pub text: String,
+    pub matched: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SearchHit {
+    pub kind: HitKind,
+    pub table_name: String,
+    pub column_name: Option<String>,
+    pub score: u32,
+    pub highlights: Highlights,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Highlights {
+    pub table: Vec<Segment>,
+    pub column: Vec<Segment>,
+    pub r#type: Vec<Segment>,
+    pub comment: Vec<Segment>,
+    pub note: Vec<Segment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct SearchIndex {
+    pub documents: Vec<Document>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum MatchLevel {
+    Fuzzy,
+    Substring,
+    WordPrefix,
+    Prefix,
+    Exact,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        match name {
+            "table" => Some(Field::Table),
+            "column" => Some(Field::Column),
+            "type" => Some(Field::Type),
+            "comment" => Some(Field::Comment),
+            "note" => Some(Field::Note),
+            _ => None,
+        }
+    }
+
+    fn weight(self) -> u32 {
+        match self {
+            Field::Table | Field::Column => 3,
+            Field::Type => 2,
+            Field::Comment | Field::Note => 1,
+        }
+    }
+}
+
+impl MatchLevel {
+    fn score(self) -> u32 {
+        match self {
+            MatchLevel::Exact => 100,
+            MatchLevel::Prefix => 70,
+            MatchLevel::WordPrefix => 50,
+            MatchLevel::Substring => 30,
+            MatchLevel::Fuzzy => 15,
+        }
+    }
+}
+
+impl Document {
+    fn value(&self, field: Field) -> Option<&str> {
+        match field {
+            Field::Table => Some(&self.table_name),
+            Field::Column => self.column_name.as_deref(),
+            Field::Type => self.column_type.as_deref(),
+            Field::Comment => Some(&self.comment),
+            Field::Note => Some(&self.note),
+        }
+    }
+
+    // Unqualified terms only look at the document's own fields, so a table name
+    // does not pull in every column of that table.
+    fn default_fields(&self) -> &'static [Field] {
+        match self.kind {
+            HitKind::Table => &[Field::Table, Field::Comment, Field::Note],
+            HitKind::Column => &[Field::Column, Field::Type, Field::Comment],
+        }
+    }
+}
+
+impl SearchIndex {
+    pub fn build(tables: &[TableOutlineResult], columns: &[SchemaColumn]) -> SearchIndex {
+        let mut documents: Vec<Document> = vec![];
+
+        for table in tables {
+            documents.push(Document {
+                kind: HitKind::Table,
+                table_name: table.table_name.clone(),
+                column_name: None,
+                column_type: None,
+                comment: table.table_comment.clone().unwrap_or_default(),
+                note: String::new(),
+            });
+            documents.extend(columns.iter()
+                .filter(|c| c.table_name == table.table_name)
+                .map(|c| Document {
+                    kind: HitKind::Column,
+                    table_name: c.table_name.clone(),
+                    column_name: Some(c.column_name.clone()),
+                    column_type: Some(c.column_type.clone()),
+                    comment: c.column_comment.clone(),
+                    note: String::new(),
+                }));
+        }
+
+        SearchIndex { documents }
+    }
+
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let terms = parse_query(query);
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let mut hits: Vec<SearchHit> = self.documents.iter()
+            .filter_map(|doc| score_document(doc, &terms))
+            .collect();
+        hits.sort_by(|a, b| b.score.cmp(&a.score)
+            .then_with(|| a.table_name.cmp(&b.table_name))
+            .then_with(|| a.column_name.cmp(&b.column_name)));
+        hits.truncate(SEARCH_RESULT_LIMIT);
+        hits
+    }
+}
+
+pub fn parse_query(query: &str) -> Vec<Term> {
+    query.split_whitespace()
+        .filter_map(|word| {
+            let (field, text) = match word.find(':') {
+                Some(i) => match Field::from_name(&word[..i].to_lowercase()) {
+                    Some(field) => (Some(field), &word[i + 1..]),
+                    None => (None, word),
+                },
+                None => (None, word),
+            };
+            let prefix = text.ends_with('*');
+            let text = text.trim_end_matches('*').to_lowercase();
+            if text.is_empty() {
+                None
+            } else {
+                Some(Term { field, text, prefix })
+            }
+        })
+        .collect()
+}
+
+fn score_document(doc: &Document, terms: &[Term]) -> Option<SearchHit> {
+    let fields = [Field::Table, Field::Column, Field::Type, Field::Comment, Field::Note];
+    let mut masks: Vec<Vec<bool>> = fields.iter()
+        .map(|f| vec![false; doc.value(*f).map(|v| v.chars().count()).unwrap_or(0)])
+        .collect();
+    let mut score = 0;
+
+    for term in terms {
+        let candidates: Vec<Field> = match term.field {
+            Some(field) => vec![field],
+            None => doc.default_fields().to_vec(),
+        };
+
+        let mut best: Option<u32> = None;
+        for field in candidates {
+            let value = match doc.value(field) {
+                Some(v) => v,
+                None => continue,
+            };
+            if let Some((level, start, end)) = match_value(value, term) {
+                let mask = &mut masks[fields.iter().position(|f| *f == field).unwrap()];
+                mask[start..end].iter_mut().for_each(|m| *m = true);
+                let s = level.score() * field.weight();
+                best = Some(best.map_or(s, |b| b.max(s)));
+            }
+        }
+
+        score += best?;
+    }
+
+    let segments = |field: Field| segments(doc.value(field).unwrap_or(""), &masks[fields.iter().position(|f| *f == field).unwrap()]);
+    Some(SearchHit {
+        kind: doc.kind,
+        table_name: doc.table_name.clone(),
+        column_name: doc.column_name.clone(),
+        score,
+        highlights: Highlights {
+            table: segments(Field::Table),
+            column: segments(Field::Column),
+            r#type: segments(Field::Type),
+            comment: segments(Field::Comment),
+            note: segments(Field::Note),
+        },
+    })
+}
+
+fn match_value(value: &str, term: &Term) -> Option<(MatchLevel, usize, usize)> {
+    let chars: Vec<char> = fold(value);
+    let needle: Vec<char> = term.text.chars().collect();
+    let n = needle.len();
+
+    if !term.prefix && chars == needle {
+        return Some((MatchLevel::Exact, 0, n));
+    }
+    if chars.starts_with(&needle) {
+        return Some((MatchLevel::Prefix, 0, n));
+    }
+
+    let words = words(&chars);
+    if let Some((start, _)) = words.iter().find(|(s, e)| chars[*s..*e].starts_with(&needle)) {
+        return Some((MatchLevel::WordPrefix, *start, start + n));
+    }
+    if term.prefix {
+        return None;
+    }
+
+    if let Some(start) = chars.windows(n).position(|w| w == needle.as_slice()) {
+        return Some((MatchLevel::Substring, start, start + n));
+    }
+
+    let max_distance = match n {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    std::iter::once((0, chars.len())).chain(words)
+        .find(|(s, e)| edit_distance(&chars[*s..*e], &needle) <= max_distance)
+        .map(|(s, e)| (MatchLevel::Fuzzy, s, e))
+}
+
+fn fold(value: &str) -> Vec<char> {
+    value.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+fn words(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut words: Vec<(usize, usize)> = vec![];
+    let mut start: Option<usize> = None;
+
+    for (i, c) in chars.iter().enumerate() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, chars.len()));
+    }
+
+    words
+}
+
+// Edit distance counting an adjacent transposition as a single edit.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j - 1] + cost).min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+fn segments(value: &str, mask: &[bool]) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = vec![];
+
+    for (c, matched) in value.chars().zip(mask.iter()) {
+        match segments.last_mut() {
+            Some(last) if last.matched == *matched => last.text.push(c),
+            _ => segments.push(Segment { text: c.to_string(), matched: *matched }),
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> SearchIndex {
+        let table = |name: &str, comment: &str| TableOutlineResult {
+            table_name: name.to_string(),
+            table_comment: Some(comment.to_string()),
+            table_fqn: format!("shop.{}", name),
+        };
+        let column = |table_name: &str, name: &str, column_type: &str, comment: &str| SchemaColumn {
+            table_name: table_name.to_string(),
+            column_name: name.to_string(),
+            data_type: column_type.to_string(),
+            column_type: column_type.to_string(),
+            column_key: String::new(),
+            nullable: false,
+            column_default: None,
+            extra: String::new(),
+            character_set_name: None,
+            collation_name: None,
+            generation_expression: None,
+            column_comment: comment.to_string(),
+        };
+
+        SearchIndex::build(
+            &[table("customers", "People who order"), table("orders", "")],
+            &[
+                column("customers", "email", "varchar(255)", "Login address"),
+                column("orders", "customer_id", "int(11)", ""),
+                column("orders", "ordered_at", "datetime", "When the customer paid"),
+            ],
+        )
+    }
+
+    fn found(hits: &[SearchHit]) -> Vec<(&str, Option<&str>)> {
+        hits.iter().map(|h| (h.table_name.as_str(), h.column_name.as_deref())).collect()
+    }
+
+    #[test]
+    fn query_parses_fields_and_prefixes() {
+        assert_eq!(parse_query("  Type:INT* order  bogus:x column:* "), vec![
+            Term { field: Some(Field::Type), text: String::from("int"), prefix: true },
+            Term { field: None, text: String::from("order"), prefix: false },
+            Term { field: None, text: String::from("bogus:x"), prefix: false },
+        ]);
+        assert!(parse_query("   ").is_empty());
+    }
+
+    #[test]
+    fn edit_distance_counts_transpositions_once() {
+        let d = |a: &str, b: &str| edit_distance(&fold(a), &fold(b));
+
+        assert_eq!(d("orders", "orders"), 0);
+        assert_eq!(d("orders", "odrers"), 1);
+        assert_eq!(d("orders", "order"), 1);
+        assert_eq!(d("customer", "custmoers"), 2);
+        assert_eq!(d("", "abc"), 3);
+    }
+
+    #[test]
+    fn exact_matches_rank_above_prefix_and_word_matches() {
+        let hits = index().search("customer");
+
+        // Both names start with the term, so the tie is broken by table name.
+        assert_eq!(found(&hits), vec![
+            ("customers", None),
+            ("orders", Some("customer_id")),
+            ("orders", Some("ordered_at")),
+        ]);
+        assert_eq!(hits[0].score, hits[1].score);
+        assert!(hits[1].score > hits[2].score);
+
+        let hits = index().search("customers");
+        assert_eq!(hits[0].table_name, "customers");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn ties_are_ordered_by_table_then_column() {
+        let hits = index().search("type:int*");
+
+        assert_eq!(found(&hits), vec![("orders", Some("customer_id"))]);
+        let hits = index().search("column:*");
+        assert!(hits.is_empty());
+
+        let hits = index().search("or*");
+        assert_eq!(found(&hits), vec![
+            ("orders", None),
+            ("orders", Some("ordered_at")),
+            ("customers", None),
+        ]);
+    }
+
+    #[test]
+    fn fuzzy_terms_tolerate_typos_but_prefix_terms_do_not() {
+        assert_eq!(found(&index().search("odrers")), vec![("orders", None)]);
+        assert!(index().search("odrers*").is_empty());
+        assert!(index().search("ordx").is_empty());
+    }
+
+    #[test]
+    fn every_term_must_match_and_matches_are_highlighted() {
+        let hits = index().search("table:orders customer");
+
+        assert_eq!(found(&hits), vec![("orders", Some("customer_id")), ("orders", Some("ordered_at"))]);
+        assert_eq!(hits[0].highlights.column, vec![
+            Segment { text: String::from("customer"), matched: true },
+            Segment { text: String::from("_id"), matched: false },
+        ]);
+        assert_eq!(hits[0].highlights.table, vec![Segment { text: String::from("orders"), matched: true }]);
+    }
+}
@@ -0,0 +1,506 @@
+use std::fmt::Display;
+use std::fs;
+use std::net::ToSocketAddrs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{
+    Arg,
+    ArgMatches,
+};
+use mysql::Opts;
+
+use crate::{
+    catalog,
+    datasource,
+    rowcount,
+    watchdog,
+};
+use crate::error::AppError;
+
+pub const CONFIG_FILE: &str = "RUSTACK_CONFIG";
+pub const BIND_ADDRESS: &str = "BIND_ADDRESS";
+pub const PORT: &str = "PORT";
+pub const LOG_LEVEL: &str = "RUST_LOG";
+pub const FEATURE_EXACT_ROW_COUNTS: &str = "FEATURE_EXACT_ROW_COUNTS";
+pub const FEATURE_INFERRED_RELATIONS: &str = "FEATURE_INFERRED_RELATIONS";
+pub const FEATURE_ADMIN: &str = "FEATURE_ADMIN";
+pub const FEATURE_PARTITION_MAX: &str = "FEATURE_PARTITION_MAX";
+
+pub const BIND_ADDRESS_DEFAULT: &str = "127.0.0.1";
+pub const PORT_DEFAULT: u16 = 8088;
+pub const LOG_LEVEL_DEFAULT: &str = "actix_web=info";
+
+pub const FEATURES: [&str; 4] = ["exact_row_counts", "inferred_relations", "admin", "partition_max"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub server: ServerSettings,
+    pub datasource: DataSourceSettings,
+    pub catalog: CatalogSettings,
+    pub row_counts: RowCountSettings,
+    pub features: Features,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerSettings {
+    pub bind_address: String,
+    pub port: u16,
+    pub log_level: String,
+}
+
+// Discrete values win over the parts of `url`, which is kept for its query parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSourceSettings {
+    pub url: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub socket: Option<String>,
+    pub username: String,
+    pub password: String,
+    pub name: String,
+    pub tls: Option<TlsSettings>,
+    pub pool_size: u32,
+    pub query_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsSettings {
+    pub ca: PathBuf,
+    pub client: Option<ClientCertificate>,
+    pub verify_peer: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogSettings {
+    pub check_interval_secs: u64,
+    pub max_age_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowCountSettings {
+    pub timeout_ms: u64,
+}
+
+// Optional parts of the UI and API. A disabled feature's routes are not registered.
+// `partition_max` is off by default: it reads MAX() of the last range partition on every
+// table page, which can scan the whole partition.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    pub exact_row_counts: bool,
+    pub inferred_relations: bool,
+    pub admin: bool,
+    pub partition_max: bool,
+}
+
+impl Default for Features {
+    fn default() -> Features {
+        Features {
+            exact_row_counts: true,
+            inferred_relations: true,
+            admin: true,
+            partition_max: false,
+        }
+    }
+}
+
+impl Features {
+    fn set(&mut self, name: &str, enabled: bool) {
+        match name {
+            "exact_row_counts" => self.exact_row_counts = enabled,
+            "inferred_relations" => self.inferred_relations = enabled,
+            "admin" => self.admin = enabled,
+            "partition_max" => self.partition_max = enabled,
+            _ => {},
+        }
+    }
+}
+
+// The shape of the TOML file. Every key is optional; environment variables and flags
+// override whatever the file sets.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsFile {
+    server: ServerFile,
+    datasource: DataSourceFile,
+    catalog: CatalogFile,
+    row_counts: RowCountFile,
+    features: FeatureFile,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ServerFile {
+    bind_address: Option<String>,
+    port: Option<u16>,
+    log_level: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct DataSourceFile {
+    url: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    socket: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    password_file: Option<PathBuf>,
+    name: Option<String>,
+    tls: TlsFile,
+    pool_size: Option<u32>,
+    query_timeout_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TlsFile {
+    ca: Option<PathBuf>,
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+    verify_peer: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct CatalogFile {
+    check_interval_secs: Option<u64>,
+    max_age_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RowCountFile {
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FeatureFile {
+    exact_row_counts: Option<bool>,
+    inferred_relations: Option<bool>,
+    admin: Option<bool>,
+    partition_max: Option<bool>,
+}
+
+// Where to find the database, for every command that connects to it.
+pub fn args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("config").long("config").short("c").value_name("FILE")
+            .help("TOML settings file [env: RUSTACK_CONFIG]"),
+        Arg::with_name("db-host").long("db-host").value_name("HOST")
+            .help("MySQL host [env: DATABASE_HOST] [default: 127.0.0.1]"),
+        Arg::with_name("db-port").long("db-port").value_name("PORT")
+            .help("MySQL port [env: DATABASE_PORT] [default: 3306]"),
+        Arg::with_name("db-socket").long("db-socket").value_name("PATH")
+            .help("Connect through this Unix socket instead of TCP [env: DATABASE_SOCKET]"),
+        Arg::with_name("db-user").long("db-user").value_name("USER")
+            .help("MySQL user [env: DATABASE_USERNAME]"),
+        Arg::with_name("db-password-file").long("db-password-file").value_name("FILE")
+            .help("Read the MySQL password from this file [env: DATABASE_PASSWORD_FILE]"),
+        Arg::with_name("db-name").long("db-name").value_name("NAME")
+            .help("Schema to browse [env: DATABASE_NAME]"),
+        Arg::with_name("db-tls-ca").long("db-tls-ca").value_name("FILE")
+            .help("Connect with TLS, trusting this PEM CA certificate [env: DATABASE_TLS_CA]"),
+        Arg::with_name("db-tls-cert").long("db-tls-cert").value_name("FILE")
+            .help("PEM client certificate [env: DATABASE_TLS_CERT]"),
+        Arg::with_name("db-tls-key").long("db-tls-key").value_name("FILE")
+            .help("PEM client key [env: DATABASE_TLS_KEY]"),
+        Arg::with_name("db-tls-verify-peer").long("db-tls-verify-peer").value_name("BOOL")
+            .help("Check the server certificate against the CA [env: DATABASE_TLS_VERIFY_PEER] [default: true]"),
+        Arg::with_name("pool-size").long("pool-size").value_name("SIZE")
+            .help("Maximum number of pooled connections [env: DATABASE_POOL_SIZE] [default: 4]"),
+        Arg::with_name("query-timeout").long("query-timeout").value_name("SECS")
+            .help("Stop queries running longer than this, 0 to disable [env: QUERY_TIMEOUT_SECS] [default: 30]"),
+    ]
+}
+
+pub fn server_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("bind").long("bind").value_name("ADDRESS")
+            .help("Address to listen on [env: BIND_ADDRESS] [default: 127.0.0.1]"),
+        Arg::with_name("port").long("port").short("p").value_name("PORT")
+            .help("Port to listen on [env: PORT] [default: 8088]"),
+        Arg::with_name("log-level").long("log-level").value_name("FILTER")
+            .help("env_logger filter, e.g. info or actix_web=debug [env: RUST_LOG] [default: actix_web=info]"),
+        Arg::with_name("catalog-check-interval").long("catalog-check-interval").value_name("SECS")
+            .help("How often to check the schema for changes [env: CATALOG_CHECK_INTERVAL_SECS] [default: 30]"),
+        Arg::with_name("catalog-max-age").long("catalog-max-age").value_name("SECS")
+            .help("Reload the schema at least this often [env: CATALOG_MAX_AGE_SECS] [default: 600]"),
+        Arg::with_name("row-count-timeout").long("row-count-timeout").value_name("MS")
+            .help("Time limit for each exact row count [env: ROW_COUNT_TIMEOUT_MS] [default: 5000]"),
+        Arg::with_name("enable").long("enable").value_name("FEATURE")
+            .multiple(true).number_of_values(1).possible_values(&FEATURES)
+            .help("Turn on an optional feature"),
+        Arg::with_name("disable").long("disable").value_name("FEATURE")
+            .multiple(true).number_of_values(1).possible_values(&FEATURES)
+            .help("Turn off an optional feature"),
+    ]
+}
+
+// Collects every problem instead of stopping at the first, so one run reports them all.
+struct Sources<'a> {
+    matches: &'a ArgMatches<'a>,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl<'a> Sources<'a> {
+    // A flag wins over the environment variable, which wins over the file.
+    fn value<T>(&mut self, from_file: Option<T>, env: &str, flag: &str) -> Option<T>
+        where T: FromStr, T::Err: Display {
+        match self.matches.value_of(flag) {
+            Some(v) => self.parse(&format!("--{}", flag), v),
+            None => self.env_value(from_file, env),
+        }
+    }
+
+    // For settings without a flag, such as the password, which would show up in `ps`.
+    fn env_value<T>(&mut self, from_file: Option<T>, env: &str) -> Option<T>
+        where T: FromStr, T::Err: Display {
+        match (self.env)(env) {
+            Some(v) => self.parse(env, &v),
+            None => from_file,
+        }
+    }
+
+    fn parse<T>(&mut self, source: &str, v: &str) -> Option<T> where T: FromStr, T::Err: Display {
+        v.parse::<T>()
+            .map_err(|e| self.problems.push(format!("{} `{}` is invalid: {}", source, v, e)))
+            .ok()
+    }
+
+    fn required(&mut self, value: Option<String>, key: &str, env: &str) -> String {
+        match value {
+            Some(ref v) if v.is_empty() => self.problems.push(format!("{} must not be empty", key)),
+            Some(v) => return v,
+            None => self.problems.push(format!("{} must be set (or {})", key, env)),
+        }
+        String::new()
+    }
+
+    fn positive<T>(&mut self, value: T, key: &str) -> T where T: Default + PartialEq + Copy {
+        if value == T::default() {
+            self.problems.push(format!("{} must be greater than 0", key));
+        }
+        value
+    }
+
+    fn datasource(&mut self, file: DataSourceFile) -> DataSourceSettings {
+        let url = self.env_value(file.url, datasource::DATABASE_URL);
+        // The URL holds the password, so the message leaves it out.
+        let from_url = url.as_ref().and_then(|u| Opts::from_url(u)
+            .map_err(|e| self.problems.push(format!("datasource.url is invalid: {}", e)))
+            .ok());
+        let url_value = |get: fn(&Opts) -> Option<&str>| from_url.as_ref().and_then(get).map(String::from);
+
+        let host = self.value(file.host, datasource::DATABASE_HOST, "db-host")
+            .or_else(|| url_value(Opts::get_ip_or_hostname))
+            .unwrap_or_else(|| String::from(datasource::DATABASE_HOST_DEFAULT));
+        let port = self.value(file.port, datasource::DATABASE_PORT, "db-port")
+            .or_else(|| from_url.as_ref().map(Opts::get_tcp_port))
+            .unwrap_or(datasource::DATABASE_PORT_DEFAULT);
+        let socket = self.value(file.socket, datasource::DATABASE_SOCKET, "db-socket")
+            .or_else(|| url_value(Opts::get_socket));
+        let username = self.value(file.username, datasource::DATABASE_USERNAME, "db-user")
+            .or_else(|| url_value(Opts::get_user));
+        let password = self.password(file.password, file.password_file)
+            .or_else(|| url_value(Opts::get_pass));
+        let name = self.value(file.name, datasource::DATABASE_NAME, "db-name")
+            .or_else(|| url_value(Opts::get_db_name));
+
+        if socket.is_none() && host.is_empty() {
+            self.problems.push(String::from("datasource.host must not be empty"));
+        }
+        if socket.as_ref().is_some_and(String::is_empty) {
+            self.problems.push(String::from("datasource.socket must not be empty"));
+        }
+        // Socket connections may authenticate by the OS user instead.
+        let password = match (password, &socket) {
+            (None, Some(_)) => String::new(),
+            (password, _) => self.required(password, "datasource.password", datasource::DATABASE_PASSWORD),
+        };
+
+        DataSourceSettings {
+            url,
+            host,
+            port,
+            socket,
+            username: self.required(username, "datasource.username", datasource::DATABASE_USERNAME),
+            password,
+            name: self.required(name, "datasource.name", datasource::DATABASE_NAME),
+            tls: self.tls(file.tls),
+            pool_size: self.value(file.pool_size, datasource::DATABASE_POOL_SIZE, "pool-size")
+                .unwrap_or(datasource::DATABASE_POOL_SIZE_DEFAULT),
+            query_timeout_secs: self.value(file.query_timeout_secs, watchdog::QUERY_TIMEOUT_SECS, "query-timeout")
+                .unwrap_or(watchdog::QUERY_TIMEOUT_SECS_DEFAULT),
+        }
+    }
+
+    // Secrets mounted into containers usually end with a newline that is not part of the password.
+    fn password(&mut self, from_file: Option<String>, path_from_file: Option<PathBuf>) -> Option<String> {
+        let password = self.env_value(from_file, datasource::DATABASE_PASSWORD);
+        let path = self.value(path_from_file, datasource::DATABASE_PASSWORD_FILE, "db-password-file");
+        match (password, path) {
+            (Some(_), Some(_)) => {
+                self.problems.push(String::from("set either datasource.password or datasource.password_file, not both"));
+                None
+            },
+            (password, None) => password,
+            (None, Some(path)) => fs::read_to_string(&path)
+                .map(|p| String::from(p.trim_end_matches(['\n', '\r'])))
+                .map_err(|e| self.problems.push(format!("could not read password file {}: {}", path.display(), e)))
+                .ok(),
+        }
+    }
+
+    fn tls(&mut self, file: TlsFile) -> Option<TlsSettings> {
+        let ca = self.value(file.ca, datasource::DATABASE_TLS_CA, "db-tls-ca");
+        let cert = self.value(file.cert, datasource::DATABASE_TLS_CERT, "db-tls-cert");
+        let key = self.value(file.key, datasource::DATABASE_TLS_KEY, "db-tls-key");
+        let verify_peer = self.value(file.verify_peer, datasource::DATABASE_TLS_VERIFY_PEER, "db-tls-verify-peer")
+            .unwrap_or(true);
+
+        for (name, path) in [("ca", &ca), ("cert", &cert), ("key", &key)] {
+            if let Some(p) = path.as_ref().filter(|p| !p.is_file()) {
+                self.problems.push(format!("datasource.tls.{} {} is not a file", name, p.display()));
+            }
+        }
+
+        match (ca, cert, key) {
+            (None, None, None) => None,
+            (None, _, _) => {
+                self.problems.push(String::from("datasource.tls.ca must be set to connect with TLS"));
+                None
+            },
+            (Some(ca), None, None) => Some(TlsSettings { ca, client: None, verify_peer }),
+            (Some(ca), Some(cert), Some(key)) => Some(TlsSettings {
+                ca,
+                client: Some(ClientCertificate { cert, key }),
+                verify_peer,
+            }),
+            _ => {
+                self.problems.push(String::from("datasource.tls.cert and datasource.tls.key must be set together"));
+                None
+            },
+        }
+    }
+
+    fn file(&mut self) -> SettingsFile {
+        let path = match self.matches.value_of("config").map(String::from).or_else(|| (self.env)(CONFIG_FILE)) {
+            Some(p) => p,
+            None => return SettingsFile::default(),
+        };
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| format!("could not read {}: {}", path, e))
+            .and_then(|text| toml::from_str(&text).map_err(|e| format!("{}: {}", path, e)));
+        parsed.unwrap_or_else(|problem| {
+            self.problems.push(problem);
+            SettingsFile::default()
+        })
+    }
+}
+
+impl Settings {
+    // Merges the file named by --config or RUSTACK_CONFIG, the environment and the flags.
+    pub fn from_matches(
+        matches: &ArgMatches,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Settings, AppError> {
+        let mut sources = Sources { matches, env, problems: vec![] };
+        let file = sources.file();
+
+        let server = ServerSettings {
+            bind_address: sources.value(file.server.bind_address, BIND_ADDRESS, "bind")
+                .unwrap_or_else(|| String::from(BIND_ADDRESS_DEFAULT)),
+            port: sources.value(file.server.port, PORT, "port").unwrap_or(PORT_DEFAULT),
+            log_level: sources.value(file.server.log_level, LOG_LEVEL, "log-level")
+                .unwrap_or_else(|| String::from(LOG_LEVEL_DEFAULT)),
+        };
+        sources.positive(server.port, "server.port");
+        if server.port != 0 && (server.bind_address.as_str(), server.port).to_socket_addrs().is_err() {
+            sources.problems.push(format!("server.bind_address `{}` is not a valid address", server.bind_address));
+        }
+        if let Some(level) = invalid_log_level(&server.log_level) {
+            sources.problems.push(format!("server.log_level `{}` is not a log level", level));
+        }
+
+        let datasource = sources.datasource(file.datasource);
+        sources.positive(datasource.port, "datasource.port");
+        sources.positive(datasource.pool_size, "datasource.pool_size");
+
+        let catalog = CatalogSettings {
+            check_interval_secs: sources.value(file.catalog.check_interval_secs, catalog::CATALOG_CHECK_INTERVAL_SECS, "catalog-check-interval")
+                .unwrap_or(catalog::CATALOG_CHECK_INTERVAL_SECS_DEFAULT),
+            max_age_secs: sources.value(file.catalog.max_age_secs, catalog::CATALOG_MAX_AGE_SECS, "catalog-max-age")
+                .unwrap_or(catalog::CATALOG_MAX_AGE_SECS_DEFAULT),
+        };
+        sources.positive(catalog.check_interval_secs, "catalog.check_interval_secs");
+
+        let row_counts = RowCountSettings {
+            timeout_ms: sources.value(file.row_counts.timeout_ms, rowcount::ROW_COUNT_TIMEOUT_MS, "row-count-timeout")
+                .unwrap_or(rowcount::ROW_COUNT_TIMEOUT_MS_DEFAULT),
+        };
+
+        let defaults = Features::default();
+        let mut features = Features {
+            exact_row_counts: sources.env_value(file.features.exact_row_counts, FEATURE_EXACT_ROW_COUNTS)
+                .unwrap_or(defaults.exact_row_counts),
+            inferred_relations: sources.env_value(file.features.inferred_relations, FEATURE_INFERRED_RELATIONS)
+                .unwrap_or(defaults.inferred_relations),
+            admin: sources.env_value(file.features.admin, FEATURE_ADMIN)
+                .unwrap_or(defaults.admin),
+            partition_max: sources.env_value(file.features.partition_max, FEATURE_PARTITION_MAX)
+                .unwrap_or(defaults.partition_max),
+        };
+        for name in matches.values_of("enable").into_iter().flatten() {
+            features.set(name, true);
+        }
+        for name in matches.values_of("disable").into_iter().flatten() {
+            features.set(name, false);
+        }
+
+        if !sources.problems.is_empty() {
+            return Err(AppError::Config(sources.problems.join("; ")));
+        }
+
+        Ok(Settings {
+            server,
+            datasource,
+            catalog,
+            row_counts,
+            features,
+        })
+    }
+}
+
+// env_logger silently ignores directives it cannot parse, so a typo would turn logging off.
+fn invalid_log_level(filters: &str) -> Option<&str> {
+    let directives = filters.split('/').next().unwrap_or("");
+    directives.split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .find_map(|d| match d.rsplit_once('=') {
+            Some((_, level)) => Some(level.trim()).filter(|level| !is_log_level(level)),
+            // A bare directive is either a level or a module path.
+            None => Some(d).filter(|d| !is_log_level(d) && !is_module_path(d)),
+        })
+}
+
+fn is_log_level(level: &str) -> bool {
+    level.parse::<log::LevelFilter>().is_ok()
+}
+
+fn is_module_path(path: &str) -> bool {
+    path.split("::").all(|segment| {
+        let mut chars = segment.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::{
+    Component,
+    Path,
+};
+
+use itertools::Itertools;
+use tera::Tera;
+
+use crate::{
+    ddl,
+    diagram,
+    lint,
+    page,
+    sql,
+};
+use crate::catalog::Catalog;
+use crate::datasource::DataSource;
+use crate::error::AppError;
+use crate::rowcount::RowCountCache;
+use crate::search::{
+    SEARCH_RESULT_LIMIT,
+    SearchHit,
+};
+use crate::settings::Features;
+use crate::sql::{
+    Routine,
+    View,
+};
+
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+// A file of the static site, by its path from the site root.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Page {
+    pub path: String,
+    pub contents: String,
+}
+
+// Renders every page with the same templates as `serve` and writes them under `dir`.
+// Returns the number of files written.
+pub fn export(datasource: &DataSource, tera: &Tera, features: Features, dir: &Path) -> Result<usize, AppError> {
+    let pages = pages(datasource, tera, features)?;
+    for page in pages.iter() {
+        write(dir, page)?;
+    }
+    Ok(pages.len())
+}
+
+fn pages(datasource: &DataSource, tera: &Tera, features: Features) -> Result<Vec<Page>, AppError> {
+    let db_name = &datasource.name;
+    let mut conn = datasource.conn_pool.get()?;
+
+    let catalog = Catalog::load(&mut conn, db_name, None)?;
+    let mut pages = catalog_pages(tera, db_name, &catalog, features)?;
+
+    let views: Vec<View> = sql::query_views(&mut conn, db_name, None)?;
+    for name in catalog.table_names() {
+        if let Some(table) = sql::query_table(&mut conn, db_name, &name, features.partition_max)? {
+            let ctx = page::table(db_name, &catalog, &views, table);
+            pages.push(html(tera, &format!("/table/{}", page::path_segment(&name)), "table.html", ctx)?);
+        }
+        if let Some(d) = sql::query_create_table(&mut conn, db_name, &name)? {
+            pages.push(text(&format!("/table/{}/ddl", page::path_segment(&name)), format!("{};\n", d)));
+        }
+    }
+
+    let view_names: Vec<String> = views.iter().map(|v| v.view_name.clone()).collect();
+    let outlines = page::view_outlines(db_name, views);
+    let mut ctx = tera::Context::new();
+    ctx.insert("views", &outlines);
+    pages.push(html(tera, "/views", "views.html", ctx)?);
+    for outline in outlines.iter() {
+        let name = &outline.view.view_name;
+        let ddl = sql::query_create_view(&mut conn, db_name, name)?;
+        pages.push(html(tera, &format!("/view/{}", page::path_segment(name)), "view.html", page::view(outline, &view_names, &ddl))?);
+        if let Some(d) = ddl {
+            pages.push(text(&format!("/table/{}/ddl", page::path_segment(name)), format!("{};\n", d)));
+        }
+    }
+
+    let routines: Vec<Routine> = sql::query_routines(&mut conn, db_name, None)?;
+    let mut ctx = tera::Context::new();
+    ctx.insert("routines", &routines);
+    pages.push(html(tera, "/routines", "routines.html", ctx)?);
+    for name in routines.iter().map(|r| &r.routine_name).unique() {
+        let overloads: Vec<&Routine> = routines.iter().filter(|r| &r.routine_name == name).collect();
+        let mut ctx = tera::Context::new();
+        ctx.insert("routine_name", name);
+        ctx.insert("routines", &overloads);
+        pages.push(html(tera, &format!("/routine/{}", page::path_segment(name)), "routine.html", ctx)?);
+    }
+
+    let mut ctx = tera::Context::new();
+    ctx.insert("triggers", &sql::query_triggers(&mut conn, db_name, None)?);
+    pages.push(html(tera, "/triggers", "triggers.html", ctx)?);
+
+    let mut ctx = tera::Context::new();
+    ctx.insert("events", &sql::query_events(&mut conn, db_name)?);
+    pages.push(html(tera, "/events", "events.html", ctx)?);
+
+    let indexes = sql::query_indexes(&mut conn, db_name, None)?;
+    let mut ctx = tera::Context::new();
+    ctx.insert("findings", &lint::lint(&catalog.foreign_keys, &indexes));
+    pages.push(html(tera, "/schema-health", "health.html", ctx)?);
+
+    pages.push(text("/ddl", ddl::load_schema_script(&mut conn, db_name)?));
+
+    Ok(pages)
+}
+
+// The pages that need nothing but the catalog. Exact row counts need a server to
+// count them, so the exported table list shows the estimates only.
+pub fn catalog_pages(tera: &Tera, db_name: &str, catalog: &Catalog, features: Features) -> Result<Vec<Page>, AppError> {
+    let features = Features { exact_row_counts: false, ..features };
+    let mut pages = vec![];
+
+    let tables = html(tera, "/tables", "tables.html", page::tables(db_name, catalog, &RowCountCache::new(), features))?;
+    pages.push(Page { path: String::from("index.html"), contents: tables.contents.clone() });
+    pages.push(tables);
+
+    let mermaid = diagram::mermaid_er(&catalog.table_names(), &catalog.foreign_keys, &catalog.unique_keys, &catalog.columns);
+    let mut ctx = tera::Context::new();
+    ctx.insert("mermaid", &mermaid);
+    pages.push(html(tera, "/diagram", "diagram.html", ctx)?);
+    pages.push(text("/diagram.mmd", mermaid));
+
+    if features.inferred_relations {
+        let mut ctx = tera::Context::new();
+        ctx.insert("relations", &catalog.inferred_relations);
+        ctx.insert("sample", &false);
+        pages.push(html(tera, "/relations/inferred", "inferred.html", ctx)?);
+    }
+
+    let json = serde_json::to_string_pretty(&catalog.search_index.documents)
+        .map_err(|e| AppError::Io(format!("could not write JSON: {}", e)))?;
+    pages.push(Page { path: String::from(SEARCH_INDEX_FILE), contents: json + "\n" });
+    // search.html runs the query in the browser against the index above.
+    let mut ctx = tera::Context::new();
+    ctx.insert("q", "");
+    ctx.insert("hits", &Vec::<SearchHit>::new());
+    ctx.insert("limit", &SEARCH_RESULT_LIMIT);
+    pages.push(html(tera, "/search", "search.html", ctx)?);
+
+    Ok(pages)
+}
+
+fn html(tera: &Tera, url: &str, template: &str, mut ctx: tera::Context) -> Result<Page, AppError> {
+    ctx.insert("static_site", &true);
+    let path = file_path(url);
+    let depth = path.matches('/').count();
+    let contents = relative_links(&tera.render(template, &ctx)?, depth);
+    Ok(Page { path, contents })
+}
+
+fn text(url: &str, contents: String) -> Page {
+    Page { path: file_path(url), contents }
+}
+
+// The file that stands in for a URL of the live site: `/table/orders` is
+// `table/orders.html`, and `/table/orders/ddl` is `table/orders.sql`. Names in the URL
+// are already `page::path_segment`s, so the file is named after the encoded name.
+pub fn file_path(url: &str) -> String {
+    let path = url.trim_start_matches('/');
+    match path {
+        "ddl" => String::from("schema.sql"),
+        "diagram.mmd" => String::from(path),
+        _ if path.ends_with("/ddl") => format!("{}.sql", path.trim_end_matches("/ddl")),
+        _ => format!("{}.html", path),
+    }
+}
+
+// Rewrites the root-relative links in a page `depth` directories below the site root,
+// so the site works from any directory and from `file://`. Query strings are dropped;
+// the pages that need one are not exported.
+pub fn relative_links(html: &str, depth: usize) -> String {
+    let up = "../".repeat(depth);
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find("=\"/") {
+        let (head, tail) = rest.split_at(start + 2);
+        out.push_str(head);
+        let end = tail.find('"').unwrap_or(tail.len());
+        let (url, after) = tail.split_at(end);
+        if url.starts_with("//") {
+            out.push_str(url);
+        } else {
+            let (path, fragment) = url.split_at(url.find('#').unwrap_or(url.len()));
+            out.push_str(&up);
+            // The file name keeps the `%` of an encoded name, which the href has to escape again.
+            out.push_str(&file_path(path.split('?').next().unwrap_or(path)).replace('%', "%25"));
+            out.push_str(fragment);
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+fn write(dir: &Path, page: &Page) -> Result<(), AppError> {
+    if !Path::new(&page.path).components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(AppError::Io(format!("refusing to write {} outside {}", page.path, dir.display())));
+    }
+    let path = dir.join(&page.path);
+    let io_error = |e: std::io::Error| AppError::Io(format!("could not write {}: {}", path.display(), e));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    fs::write(&path, &page.contents).map_err(io_error)
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::{
+    self,
+    Read,
+};
+
+use r2d2::PooledConnection;
+use r2d2_mysql::MysqlConnectionManager;
+
+use crate::error::AppError;
+use crate::sql::{
+    self,
+    CheckConstraint,
+    ForeignKey,
+    Index,
+    Partitioning,
+    Routine,
+    SchemaColumn,
+    Trigger,
+    View,
+};
+
+// Bumped when a field is renamed or removed, so old files are rejected instead of misread.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+// The schema as JSON, for committing next to the code and comparing with `rustack diff`.
+// It carries no timestamp, so taking a snapshot of an unchanged schema rewrites the same file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    pub snapshot_version: u32,
+    pub database: String,
+    pub tables: Vec<SnapshotTable>,
+    pub columns: Vec<SchemaColumn>,
+    pub indexes: Vec<Index>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub views: Vec<View>,
+    pub routines: Vec<Routine>,
+    pub triggers: Vec<Trigger>,
+}
+
+// The table options that are part of its definition; sizes and auto_increment are left out.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SnapshotTable {
+    pub table_name: String,
+    pub table_comment: Option<String>,
+    pub engine: Option<String>,
+    pub table_collation: Option<String>,
+    pub create_options: Option<String>,
+    pub check_constraints: Vec<CheckConstraint>,
+    pub partitioning: Option<Partitioning>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    snapshot_version: u32,
+}
+
+impl Snapshot {
+    pub fn take(
+        conn: &mut PooledConnection<MysqlConnectionManager>,
+        db_name: &str,
+    ) -> mysql::Result<Snapshot> {
+        let mut snapshot = Snapshot {
+            snapshot_version: SNAPSHOT_VERSION,
+            database: String::from(db_name),
+            tables: vec![],
+            columns: sql::query_schema_columns(conn, String::from(db_name))?,
+            indexes: sql::query_indexes(conn, db_name, None)?,
+            foreign_keys: sql::query_foreign_keys(conn, String::from(db_name))?,
+            views: sql::query_views(conn, db_name, None)?,
+            routines: sql::query_routines(conn, db_name, None)?,
+            triggers: sql::query_triggers(conn, db_name, None)?,
+        };
+
+        for outline in sql::query_table_outline(conn, String::from(db_name))? {
+            let size = sql::query_table_size(conn, db_name, &outline.table_name)?;
+            let partitioning = sql::query_partitioning(conn, db_name, &outline.table_name)?;
+            snapshot.tables.push(SnapshotTable {
+                check_constraints: sql::query_check_constraints(conn, db_name, &outline.table_name)?,
+                partitioning,
+                engine: size.as_ref().and_then(|s| s.engine.clone()),
+                table_collation: size.as_ref().and_then(|s| s.table_collation.clone()),
+                create_options: size.and_then(|s| s.create_options),
+                table_name: outline.table_name,
+                table_comment: outline.table_comment,
+            });
+        }
+
+        // Statistics and timestamps change without any change to the schema, and would
+        // make a committed snapshot churn.
+        for index in snapshot.indexes.iter_mut() {
+            index.cardinality = None;
+            for column in index.columns.iter_mut() {
+                column.cardinality = None;
+            }
+        }
+        for partition in snapshot.tables.iter_mut()
+            .filter_map(|t| t.partitioning.as_mut())
+            .flat_map(|p| p.partitions.iter_mut()) {
+            partition.table_rows = 0;
+            partition.data_length = 0;
+            partition.index_length = 0;
+        }
+        for routine in snapshot.routines.iter_mut() {
+            routine.created = None;
+            routine.last_altered = None;
+        }
+        for trigger in snapshot.triggers.iter_mut() {
+            trigger.created = None;
+        }
+        Ok(snapshot)
+    }
+
+    // Reads a snapshot file, or standard input when the path is `-`.
+    pub fn read(path: &str) -> Result<Snapshot, AppError> {
+        let mut text = String::new();
+        let read = if path == "-" {
+            io::stdin().read_to_string(&mut text).map(|_| ())
+        } else {
+            fs::read_to_string(path).map(|t| text = t)
+        };
+        read.map_err(|e| AppError::Io(format!("could not read {}: {}", path, e)))?;
+
+        let not_a_snapshot = |e: serde_json::Error| AppError::Input(format!("{} is not a snapshot: {}", path, e));
+        let header: SnapshotHeader = serde_json::from_str(&text).map_err(not_a_snapshot)?;
+        if header.snapshot_version != SNAPSHOT_VERSION {
+            return Err(AppError::Input(format!(
+                "{} has snapshot version {}, expected {}",
+                path, header.snapshot_version, SNAPSHOT_VERSION,
+            )));
+        }
+        serde_json::from_str(&text).map_err(not_a_snapshot)
+    }
+}
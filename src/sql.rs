@@ -13,11 +13,46 @@ use r2d2_mysql::MysqlConnectionManager;
 
 use self::r2d2::PooledConnection;
 
+use crate::cardinality::{
+    Cardinality,
+    Junction,
+    PRIMARY_KEY_NAME,
+};
+use crate::domain;
+use crate::inference::{
+    Containment,
+    InferredRelation,
+};
+use crate::partition;
+
+pub const ER_QUERY_TIMEOUT: u16 = 3024;
+pub const ER_QUERY_INTERRUPTED: u16 = 1317;
+pub const ER_BAD_FIELD_ERROR: u16 = 1054;
+pub const ER_UNKNOWN_TABLE: u16 = 1109;
+pub const ER_NO_SUCH_TABLE: u16 = 1146;
+pub const ER_WRONG_OBJECT: u16 = 1347;
+pub const ER_UNKNOWN_SYSTEM_VARIABLE: u16 = 1193;
+
+pub(crate) fn has_code(error: &mysql::Error, code: u16) -> bool {
+    match error {
+        mysql::Error::MySqlError(e) => e.code == code,
+        _ => false,
+    }
+}
+
+// True for a query stopped by max_execution_time or by KILL QUERY.
+pub fn is_timeout(error: &mysql::Error) -> bool {
+    match error {
+        mysql::Error::MySqlError(e) => e.code == ER_QUERY_TIMEOUT || e.code == ER_QUERY_INTERRUPTED,
+        _ => false,
+    }
+}
+
 pub fn query_table_outline(
     conn: &mut PooledConnection<MysqlConnectionManager>,
     param: String
-) -> Vec<TableOutlineResult> {
-    return conn.prep_exec(r#"
+) -> mysql::Result<Vec<TableOutlineResult>> {
+    conn.prep_exec(r#"
             SELECT
                 tbl.table_name      AS table_name,
                 tbl.table_comment   AS table_comment,
@@ -33,64 +68,107 @@ pub fn query_table_outline(
             "#, params!{
                 "param_schema_name" => param
             })
-        .map::<Vec<TableOutlineResult>, _>(|result| {
-            result
-                .map(|x| x.unwrap())
+        .and_then(|result| result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<Vec<TableOutlineResult>, _>(|rows| {
+            rows.into_iter()
                 .map(|row| {
-                    let (table_name, table_comment, table_fqn) = mysql::from_row(row);
-                    TableOutlineResult {
+                    let (table_name, table_comment, table_fqn) = mysql::from_row_opt(row)?;
+                    Ok(TableOutlineResult {
                         table_name,
                         table_comment,
                         table_fqn,
-                    }
+                    })
                 }).collect()
-        }).unwrap();
+        })
 }
 
 pub fn query_table_size_map(
     conn: &mut PooledConnection<MysqlConnectionManager>,
     param: String
-) -> HashMap<String, TableSizeResult> {
-    return conn.prep_exec(r#"
-            SELECT
-                tbl.table_name          AS table_name,
-                count(col.column_name)  AS column_count,
-                tbl.table_rows          AS row_count
-            FROM
-                information_schema.tables tbl
-            INNER JOIN
-                information_schema.columns col
-                    ON  tbl.table_schema  = col.table_schema
-                    AND tbl.table_name    = col.table_name
-            WHERE
-                tbl.table_schema  = :param_schema_name
-            AND tbl.table_type    = 'BASE TABLE'
-            GROUP BY
-                tbl.table_name,
-                tbl.table_rows
-            "#, params!{
-                "param_schema_name" => param
-            })
-        .map::<HashMap<String, TableSizeResult>, _>(|result| {
-            let size_result_vec: Vec<_> = result
-                .map(|x| x.unwrap())
-                .map(|row| {
-                    let (table_name, columns, rows) = mysql::from_row(row);
-                    (table_name, TableSizeResult {
-                        columns,
-                        rows,
-                    })
-                }).collect_vec();
+) -> mysql::Result<HashMap<String, TableSizeResult>> {
+    query_table_sizes(conn, &param, None).map(|sizes| sizes.into_iter().collect())
+}
+
+pub fn query_table_size(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+) -> mysql::Result<Option<TableSizeResult>> {
+    query_table_sizes(conn, db_name, Some(table_name))
+        .map(|sizes| sizes.into_iter().map(|(_, size)| size).next())
+}
 
-            size_result_vec.to_vec().into_iter().collect()
-        }).unwrap();
+fn query_table_sizes(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: Option<&str>,
+) -> mysql::Result<Vec<(String, TableSizeResult)>> {
+    conn.prep_exec(r#"
+        SELECT
+            tbl.table_name                AS out_table_name,
+            (
+                SELECT
+                    count(col.column_name)
+                FROM
+                    information_schema.columns col
+                WHERE
+                    col.table_schema  = tbl.table_schema
+                AND col.table_name    = tbl.table_name
+            )                             AS out_column_count,
+            COALESCE(tbl.table_rows, 0)   AS out_row_count,
+            tbl.engine                    AS out_engine,
+            tbl.row_format                AS out_row_format,
+            COALESCE(tbl.data_length, 0)  AS out_data_length,
+            COALESCE(tbl.index_length, 0) AS out_index_length,
+            COALESCE(tbl.data_free, 0)    AS out_data_free,
+            tbl.auto_increment            AS out_auto_increment,
+            tbl.table_collation           AS out_table_collation,
+            NULLIF(
+                tbl.create_options, ''
+            )                             AS out_create_options,
+            DATE_FORMAT(
+                tbl.create_time, '%Y-%m-%d %H:%i:%s'
+            )                             AS out_create_time,
+            DATE_FORMAT(
+                tbl.update_time, '%Y-%m-%d %H:%i:%s'
+            )                             AS out_update_time
+        FROM
+            information_schema.tables tbl
+        WHERE
+            tbl.table_schema  = :in_db_name
+        AND tbl.table_type    = 'BASE TABLE'
+        AND (:in_table_name IS NULL OR tbl.table_name = :in_table_name)
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_table_name" => table_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<(String, TableSizeResult)>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| {
+                Ok((take_val::<String>(&r, "out_table_name")?, TableSizeResult {
+                    columns: take_val::<u32>(&r, "out_column_count")?,
+                    rows: take_val::<u64>(&r, "out_row_count")?,
+                    engine: take_nullable_val::<String>(&r, "out_engine"),
+                    row_format: take_nullable_val::<String>(&r, "out_row_format"),
+                    data_length: take_val::<u64>(&r, "out_data_length")?,
+                    index_length: take_val::<u64>(&r, "out_index_length")?,
+                    data_free: take_val::<u64>(&r, "out_data_free")?,
+                    auto_increment: take_nullable_val::<u64>(&r, "out_auto_increment"),
+                    table_collation: take_nullable_val::<String>(&r, "out_table_collation"),
+                    create_options: take_nullable_val::<String>(&r, "out_create_options"),
+                    create_time: take_nullable_val::<String>(&r, "out_create_time"),
+                    update_time: take_nullable_val::<String>(&r, "out_update_time"),
+                }))
+            })
+            .collect()
+    })
 }
 
 pub fn query_table_referencing_count_to_parent_map(
     conn: &mut PooledConnection<MysqlConnectionManager>,
     param: String
-) -> HashMap<String, u32> {
-    return conn.prep_exec(r#"
+) -> mysql::Result<HashMap<String, u32>> {
+    conn.prep_exec(r#"
             SELECT
                 table_name      AS table_name,
                 sum(col_count)  AS ref_count
@@ -111,23 +189,21 @@ pub fn query_table_referencing_count_to_parent_map(
             "#, params!{
                 "param_schema_name" => param
             })
-        .map::<HashMap<String, u32>, _>(|result| {
-            let size_result_vec: Vec<_> = result
-                .map(|x| x.unwrap())
-                .map(|row| {
-                    let (table_name, ref_count) = mysql::from_row(row);
-                    (table_name, ref_count)
-                }).collect_vec();
+        .and_then(|result| result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<HashMap<String, u32>, _>(|rows| {
+            let size_result_vec: Vec<(String, u32)> = rows.into_iter()
+                .map(mysql::from_row_opt)
+                .collect::<Result<_, _>>()?;
 
-            size_result_vec.to_vec().into_iter().collect()
-        }).unwrap();
+            Ok(size_result_vec.into_iter().collect())
+        })
 }
 
 pub fn query_table_referenced_count_from_children_map(
     conn: &mut PooledConnection<MysqlConnectionManager>,
     param: String
-) -> HashMap<String, u32> {
-    return conn.prep_exec(r#"
+) -> mysql::Result<HashMap<String, u32>> {
+    conn.prep_exec(r#"
             SELECT
                 table_name AS table_name,
                 sum(count) AS ref_count
@@ -149,23 +225,847 @@ pub fn query_table_referenced_count_from_children_map(
             "#, params!{
                 "param_schema_name" => param
             })
-        .map::<HashMap<String, u32>, _>(|result| {
-            let size_result_vec: Vec<_> = result
-                .map(|x| x.unwrap())
-                .map(|row| {
-                    let (table_name, ref_count) = mysql::from_row(row);
-                    (table_name, ref_count)
-                }).collect_vec();
+        .and_then(|result| result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<HashMap<String, u32>, _>(|rows| {
+            let size_result_vec: Vec<(String, u32)> = rows.into_iter()
+                .map(mysql::from_row_opt)
+                .collect::<Result<_, _>>()?;
+
+            Ok(size_result_vec.into_iter().collect())
+        })
+}
+
+pub fn query_catalog_fingerprint(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+) -> mysql::Result<String> {
+    conn.prep_exec(r#"
+        SELECT
+            CONCAT_WS(':',
+                (
+                    SELECT
+                        COUNT(*)
+                    FROM
+                        information_schema.tables tbl
+                    WHERE
+                        tbl.table_schema = :in_db_name
+                ),
+                (
+                    SELECT
+                        COALESCE(SUM(CRC32(CONCAT_WS('|',
+                            tbl.table_name, tbl.table_type, tbl.table_comment, tbl.create_time
+                        ))), 0)
+                    FROM
+                        information_schema.tables tbl
+                    WHERE
+                        tbl.table_schema = :in_db_name
+                ),
+                (
+                    SELECT
+                        COALESCE(SUM(CRC32(CONCAT_WS('|',
+                            col.table_name, col.column_name, col.ordinal_position, col.column_type,
+                            col.is_nullable, col.column_key, col.column_default, col.extra, col.column_comment
+                        ))), 0)
+                    FROM
+                        information_schema.columns col
+                    WHERE
+                        col.table_schema = :in_db_name
+                ),
+                (
+                    SELECT
+                        COALESCE(SUM(CRC32(CONCAT_WS('|',
+                            stat.table_name, stat.index_name, stat.seq_in_index, stat.column_name,
+                            stat.non_unique, stat.sub_part, stat.index_type
+                        ))), 0)
+                    FROM
+                        information_schema.statistics stat
+                    WHERE
+                        stat.table_schema = :in_db_name
+                ),
+                (
+                    SELECT
+                        COALESCE(SUM(CRC32(CONCAT_WS('|',
+                            kcu.constraint_name, kcu.table_name, kcu.column_name, kcu.ordinal_position,
+                            kcu.referenced_table_name, kcu.referenced_column_name
+                        ))), 0)
+                    FROM
+                        information_schema.key_column_usage kcu
+                    WHERE
+                        kcu.table_schema = :in_db_name
+                )
+            )                             AS out_fingerprint
+    "#, params!{
+        "in_db_name" => db_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<String, _>(|rows| {
+        rows.first()
+            .map(|r| take_val::<String>(r, "out_fingerprint"))
+            .unwrap_or_else(|| Ok(String::new()))
+    })
+}
+
+pub fn query_foreign_keys(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    param: String
+) -> mysql::Result<Vec<ForeignKey>> {
+    conn.prep_exec(r#"
+            SELECT
+                fk.constraint_name          AS constraint_name,
+                fk.table_name               AS table_name,
+                fk.column_name              AS column_name,
+                fk.referenced_table_name    AS referenced_table_name,
+                fk.referenced_column_name   AS referenced_column_name
+            FROM
+                information_schema.key_column_usage fk
+            WHERE
+                fk.table_schema             = :param_schema_name
+            AND fk.referenced_table_schema  = :param_schema_name
+            AND fk.referenced_table_name IS NOT NULL
+            ORDER BY
+                fk.table_name,
+                fk.constraint_name,
+                fk.ordinal_position
+            "#, params!{
+                "param_schema_name" => param
+            })
+        .and_then(|result| result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<Vec<ForeignKey>, _>(|rows| {
+            let fk_columns: Vec<(String, String, String, String, String)> = rows.into_iter()
+                .map(mysql::from_row_opt)
+                .collect::<Result<_, _>>()?;
+
+            Ok(fk_columns.into_iter()
+                .group_by(|(constraint_name, table_name, _, _, _)| {
+                    (table_name.clone(), constraint_name.clone())
+                })
+                .into_iter()
+                .map(|((table_name, constraint_name), group)| {
+                    let columns: Vec<_> = group.collect();
+                    ForeignKey {
+                        constraint_name,
+                        table_name,
+                        column_names: columns.iter().map(|c| c.2.clone()).collect(),
+                        referenced_table_name: columns[0].3.clone(),
+                        referenced_column_names: columns.iter().map(|c| c.4.clone()).collect(),
+                    }
+                })
+                .collect())
+        })
+}
+
+pub fn query_schema_columns(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    param: String
+) -> mysql::Result<Vec<SchemaColumn>> {
+    conn.prep_exec(r#"
+            SELECT
+                col.table_name          AS table_name,
+                col.column_name         AS column_name,
+                col.data_type           AS data_type,
+                col.column_type         AS column_type,
+                col.column_key          AS column_key,
+                (col.is_nullable = 'YES')
+                                        AS nullable,
+                col.column_default      AS column_default,
+                col.extra               AS extra,
+                col.character_set_name  AS character_set_name,
+                col.collation_name      AS collation_name,
+                NULLIF(
+                    col.generation_expression, ''
+                )                       AS generation_expression,
+                col.column_comment      AS column_comment
+            FROM
+                information_schema.columns col
+            INNER JOIN
+                information_schema.tables tbl
+                    ON  tbl.table_schema  = col.table_schema
+                    AND tbl.table_name    = col.table_name
+            WHERE
+                col.table_schema  = :param_schema_name
+            AND tbl.table_type    = 'BASE TABLE'
+            ORDER BY
+                col.table_name,
+                col.ordinal_position
+            "#, params!{
+                "param_schema_name" => param
+            })
+        .and_then(|result| result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<Vec<SchemaColumn>, _>(|rows| {
+            rows.into_iter()
+                .map(|r| Ok(SchemaColumn {
+                    table_name: take_val::<String>(&r, "table_name")?,
+                    column_name: take_val::<String>(&r, "column_name")?,
+                    data_type: take_val::<String>(&r, "data_type")?,
+                    column_type: take_val::<String>(&r, "column_type")?,
+                    column_key: take_val::<String>(&r, "column_key")?,
+                    nullable: take_val::<bool>(&r, "nullable")?,
+                    column_default: take_nullable_val::<String>(&r, "column_default"),
+                    extra: take_val::<String>(&r, "extra")?,
+                    character_set_name: take_nullable_val::<String>(&r, "character_set_name"),
+                    collation_name: take_nullable_val::<String>(&r, "collation_name"),
+                    generation_expression: take_nullable_val::<String>(&r, "generation_expression"),
+                    column_comment: take_val::<String>(&r, "column_comment")?,
+                }))
+                .collect()
+        })
+}
+
+pub fn query_unique_keys(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    param: String
+) -> mysql::Result<Vec<UniqueKey>> {
+    conn.prep_exec(r#"
+            SELECT
+                stat.table_name     AS table_name,
+                stat.index_name     AS index_name,
+                stat.column_name    AS column_name
+            FROM
+                information_schema.statistics stat
+            WHERE
+                stat.table_schema = :param_schema_name
+            AND stat.non_unique   = 0
+            ORDER BY
+                stat.table_name,
+                stat.index_name,
+                stat.seq_in_index
+            "#, params!{
+                "param_schema_name" => param
+            })
+        .and_then(|result| result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<Vec<UniqueKey>, _>(|rows| {
+            let key_columns: Vec<(String, String, String)> = rows.into_iter()
+                .map(mysql::from_row_opt)
+                .collect::<Result<_, _>>()?;
+
+            Ok(key_columns.into_iter()
+                .group_by(|(table_name, index_name, _)| (table_name.clone(), index_name.clone()))
+                .into_iter()
+                .map(|((table_name, index_name), group)| UniqueKey {
+                    table_name,
+                    index_name,
+                    column_names: group.map(|(_, _, c)| c).collect(),
+                })
+                .collect())
+        })
+}
 
-            size_result_vec.to_vec().into_iter().collect()
-        }).unwrap();
+pub fn query_indexes(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: Option<&str>,
+) -> mysql::Result<Vec<Index>> {
+    // is_visible only exists since MySQL 8.0, so older servers fall back to a constant.
+    // Any other error, a timeout included, is not worth a second try.
+    query_index_columns(conn, db_name, table_name, "stat.is_visible")
+        .or_else(|e| if has_code(&e, ER_BAD_FIELD_ERROR) {
+            query_index_columns(conn, db_name, table_name, "'YES'")
+        } else {
+            Err(e)
+        })
+        .map(|index_columns| {
+            index_columns.into_iter()
+                .group_by(|(table_name, index_name, _, _, _, _)| (table_name.clone(), index_name.clone()))
+                .into_iter()
+                .map(|((table_name, index_name), group)| {
+                    let rows: Vec<_> = group.collect();
+                    let (_, _, unique, index_type, visible, _) = rows[0].clone();
+                    Index {
+                        table_name,
+                        primary: index_name == PRIMARY_KEY_NAME,
+                        index_name,
+                        unique,
+                        index_type,
+                        visible,
+                        cardinality: rows.last().and_then(|r| r.5.cardinality),
+                        columns: rows.into_iter().map(|r| r.5).collect(),
+                    }
+                })
+                .collect()
+        })
+}
+
+type IndexColumnRow = (String, String, bool, String, bool, IndexColumn);
+
+fn query_index_columns(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: Option<&str>,
+    visible_expr: &str,
+) -> mysql::Result<Vec<IndexColumnRow>> {
+    conn.prep_exec(format!(r#"
+        SELECT
+            stat.table_name           AS out_table_name,
+            stat.index_name           AS out_index_name,
+            (stat.non_unique = 0)     AS out_unique,
+            stat.index_type           AS out_index_type,
+            ({visible} = 'YES')       AS out_visible,
+            stat.column_name          AS out_column_name,
+            stat.sub_part             AS out_sub_part,
+            stat.cardinality          AS out_cardinality
+        FROM
+            information_schema.statistics stat
+        WHERE
+            stat.table_schema = :in_db_name
+        AND (:in_table_name IS NULL OR stat.table_name = :in_table_name)
+        ORDER BY
+            stat.table_name,
+            stat.index_name = 'PRIMARY' DESC,
+            stat.index_name,
+            stat.seq_in_index
+    "#, visible = visible_expr), params!{
+        "in_db_name" => db_name,
+        "in_table_name" => table_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<IndexColumnRow>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok((
+                take_val::<String>(&r, "out_table_name")?,
+                take_val::<String>(&r, "out_index_name")?,
+                take_val::<bool>(&r, "out_unique")?,
+                take_val::<String>(&r, "out_index_type")?,
+                take_val::<bool>(&r, "out_visible")?,
+                IndexColumn {
+                    column_name: take_nullable_val::<String>(&r, "out_column_name"),
+                    sub_part: take_nullable_val::<u32>(&r, "out_sub_part"),
+                    cardinality: take_nullable_val::<u64>(&r, "out_cardinality"),
+                },
+            )))
+            .collect()
+    })
+}
+
+pub fn query_views(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    view_name: Option<&str>,
+) -> mysql::Result<Vec<View>> {
+    conn.prep_exec(r#"
+        SELECT
+            vw.table_name         AS out_view_name,
+            vw.view_definition    AS out_view_definition,
+            vw.check_option       AS out_check_option,
+            vw.is_updatable       AS out_is_updatable,
+            vw.definer            AS out_definer,
+            vw.security_type      AS out_security_type,
+            tbl.table_comment     AS out_view_comment
+        FROM
+            information_schema.views vw
+        INNER JOIN
+            information_schema.tables tbl
+            ON  tbl.table_schema  = vw.table_schema
+            AND tbl.table_name    = vw.table_name
+        WHERE
+            vw.table_schema = :in_db_name
+        AND (:in_view_name IS NULL OR vw.table_name = :in_view_name)
+        ORDER BY
+            vw.table_name
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_view_name" => view_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<View>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok(View {
+                view_name: take_val::<String>(&r, "out_view_name")?,
+                view_definition: take_val::<String>(&r, "out_view_definition")?,
+                check_option: take_val::<String>(&r, "out_check_option")?,
+                is_updatable: take_val::<String>(&r, "out_is_updatable")? == "YES",
+                definer: take_val::<String>(&r, "out_definer")?,
+                security_type: take_val::<String>(&r, "out_security_type")?,
+                view_comment: take_nullable_val::<String>(&r, "out_view_comment"),
+            }))
+            .collect()
+    })
+}
+
+pub fn query_routines(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    routine_name: Option<&str>,
+) -> mysql::Result<Vec<Routine>> {
+    let parameters = query_routine_parameters(conn, db_name, routine_name)?;
+
+    conn.prep_exec(r#"
+        SELECT
+            rt.routine_name           AS out_routine_name,
+            rt.routine_type           AS out_routine_type,
+            rt.dtd_identifier         AS out_return_type,
+            rt.routine_definition     AS out_routine_definition,
+            rt.is_deterministic       AS out_is_deterministic,
+            rt.sql_data_access        AS out_sql_data_access,
+            rt.security_type          AS out_security_type,
+            rt.definer                AS out_definer,
+            rt.routine_comment        AS out_routine_comment,
+            DATE_FORMAT(
+                rt.created, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_created,
+            DATE_FORMAT(
+                rt.last_altered, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_last_altered
+        FROM
+            information_schema.routines rt
+        WHERE
+            rt.routine_schema = :in_db_name
+        AND (:in_routine_name IS NULL OR rt.routine_name = :in_routine_name)
+        ORDER BY
+            rt.routine_type,
+            rt.routine_name
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_routine_name" => routine_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<Routine>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| {
+                let routine_name = take_val::<String>(&r, "out_routine_name")?;
+                let routine_type = take_val::<String>(&r, "out_routine_type")?;
+                Ok(Routine {
+                    parameters: parameters.iter()
+                        .filter(|p| p.0 == routine_name && p.1 == routine_type)
+                        .map(|p| p.2.clone())
+                        .collect(),
+                    routine_name,
+                    routine_type,
+                    return_type: take_nullable_val::<String>(&r, "out_return_type"),
+                    routine_definition: take_nullable_val::<String>(&r, "out_routine_definition"),
+                    is_deterministic: take_val::<String>(&r, "out_is_deterministic")? == "YES",
+                    sql_data_access: take_val::<String>(&r, "out_sql_data_access")?,
+                    security_type: take_val::<String>(&r, "out_security_type")?,
+                    definer: take_val::<String>(&r, "out_definer")?,
+                    routine_comment: take_nullable_val::<String>(&r, "out_routine_comment"),
+                    created: take_nullable_val::<String>(&r, "out_created"),
+                    last_altered: take_nullable_val::<String>(&r, "out_last_altered"),
+                })
+            })
+            .collect()
+    })
+}
+
+fn query_routine_parameters(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    routine_name: Option<&str>,
+) -> mysql::Result<Vec<(String, String, RoutineParameter)>> {
+    conn.prep_exec(r#"
+        SELECT
+            prm.specific_name         AS out_routine_name,
+            prm.routine_type          AS out_routine_type,
+            prm.parameter_mode        AS out_parameter_mode,
+            prm.parameter_name        AS out_parameter_name,
+            prm.dtd_identifier        AS out_parameter_type
+        FROM
+            information_schema.parameters prm
+        WHERE
+            prm.specific_schema   = :in_db_name
+        AND prm.ordinal_position  > 0
+        AND (:in_routine_name IS NULL OR prm.specific_name = :in_routine_name)
+        ORDER BY
+            prm.specific_name,
+            prm.ordinal_position
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_routine_name" => routine_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<(String, String, RoutineParameter)>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok((
+                take_val::<String>(&r, "out_routine_name")?,
+                take_val::<String>(&r, "out_routine_type")?,
+                RoutineParameter {
+                    parameter_mode: take_nullable_val::<String>(&r, "out_parameter_mode"),
+                    parameter_name: take_val::<String>(&r, "out_parameter_name")?,
+                    parameter_type: take_val::<String>(&r, "out_parameter_type")?,
+                },
+            )))
+            .collect()
+    })
+}
+
+pub fn query_triggers(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: Option<&str>,
+) -> mysql::Result<Vec<Trigger>> {
+    conn.prep_exec(r#"
+        SELECT
+            trg.trigger_name          AS out_trigger_name,
+            trg.event_object_table    AS out_table_name,
+            trg.action_timing         AS out_action_timing,
+            trg.event_manipulation    AS out_event_manipulation,
+            trg.action_order          AS out_action_order,
+            trg.action_orientation    AS out_action_orientation,
+            trg.action_statement      AS out_action_statement,
+            trg.definer               AS out_definer,
+            DATE_FORMAT(
+                trg.created, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_created
+        FROM
+            information_schema.triggers trg
+        WHERE
+            trg.trigger_schema = :in_db_name
+        AND (:in_table_name IS NULL OR trg.event_object_table = :in_table_name)
+        ORDER BY
+            trg.event_object_table,
+            trg.action_timing,
+            trg.event_manipulation,
+            trg.action_order
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_table_name" => table_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<Trigger>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok(Trigger {
+                trigger_name: take_val::<String>(&r, "out_trigger_name")?,
+                table_name: take_val::<String>(&r, "out_table_name")?,
+                action_timing: take_val::<String>(&r, "out_action_timing")?,
+                event_manipulation: take_val::<String>(&r, "out_event_manipulation")?,
+                action_order: take_val::<u32>(&r, "out_action_order")?,
+                action_orientation: take_val::<String>(&r, "out_action_orientation")?,
+                action_statement: take_val::<String>(&r, "out_action_statement")?,
+                definer: take_val::<String>(&r, "out_definer")?,
+                created: take_nullable_val::<String>(&r, "out_created"),
+            }))
+            .collect()
+    })
+}
+
+pub fn query_events(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+) -> mysql::Result<Vec<Event>> {
+    conn.prep_exec(r#"
+        SELECT
+            evt.event_name            AS out_event_name,
+            evt.event_type            AS out_event_type,
+            DATE_FORMAT(
+                evt.execute_at, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_execute_at,
+            evt.interval_value        AS out_interval_value,
+            evt.interval_field        AS out_interval_field,
+            DATE_FORMAT(
+                evt.starts, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_starts,
+            DATE_FORMAT(
+                evt.ends, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_ends,
+            evt.status                AS out_status,
+            evt.on_completion         AS out_on_completion,
+            evt.event_definition      AS out_event_definition,
+            evt.definer               AS out_definer,
+            DATE_FORMAT(
+                evt.last_executed, '%Y-%m-%d %H:%i:%s'
+            )                         AS out_last_executed,
+            evt.event_comment         AS out_event_comment
+        FROM
+            information_schema.events evt
+        WHERE
+            evt.event_schema = :in_db_name
+        ORDER BY
+            evt.event_name
+    "#, params!{
+        "in_db_name" => db_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<Event>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok(Event {
+                event_name: take_val::<String>(&r, "out_event_name")?,
+                event_type: take_val::<String>(&r, "out_event_type")?,
+                execute_at: take_nullable_val::<String>(&r, "out_execute_at"),
+                interval_value: take_nullable_val::<String>(&r, "out_interval_value"),
+                interval_field: take_nullable_val::<String>(&r, "out_interval_field"),
+                starts: take_nullable_val::<String>(&r, "out_starts"),
+                ends: take_nullable_val::<String>(&r, "out_ends"),
+                status: take_val::<String>(&r, "out_status")?,
+                on_completion: take_val::<String>(&r, "out_on_completion")?,
+                event_definition: take_val::<String>(&r, "out_event_definition")?,
+                definer: take_val::<String>(&r, "out_definer")?,
+                last_executed: take_nullable_val::<String>(&r, "out_last_executed"),
+                event_comment: take_nullable_val::<String>(&r, "out_event_comment"),
+            }))
+            .collect()
+    })
+}
+
+pub fn query_partitioning(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+) -> mysql::Result<Option<Partitioning>> {
+    let rows: Vec<PartitionRow> = conn.prep_exec(r#"
+        SELECT
+            prt.partition_method              AS out_partition_method,
+            prt.partition_expression          AS out_partition_expression,
+            prt.subpartition_method           AS out_subpartition_method,
+            prt.subpartition_expression       AS out_subpartition_expression,
+            prt.partition_name                AS out_partition_name,
+            prt.partition_description         AS out_partition_description,
+            SUM(COALESCE(prt.table_rows, 0))  AS out_table_rows,
+            SUM(COALESCE(prt.data_length, 0)) AS out_data_length,
+            SUM(COALESCE(prt.index_length, 0))
+                                              AS out_index_length
+        FROM
+            information_schema.partitions prt
+        WHERE
+            prt.table_schema  = :in_db_name
+        AND prt.table_name    = :in_table_name
+        AND prt.partition_name IS NOT NULL
+        GROUP BY
+            prt.partition_method,
+            prt.partition_expression,
+            prt.subpartition_method,
+            prt.subpartition_expression,
+            prt.partition_name,
+            prt.partition_description,
+            prt.partition_ordinal_position
+        ORDER BY
+            prt.partition_ordinal_position
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_table_name" => table_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<_>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok((
+                take_nullable_val::<String>(&r, "out_partition_method"),
+                take_nullable_val::<String>(&r, "out_partition_expression"),
+                take_nullable_val::<String>(&r, "out_subpartition_method"),
+                take_nullable_val::<String>(&r, "out_subpartition_expression"),
+                Partition {
+                    partition_name: take_val::<String>(&r, "out_partition_name")?,
+                    partition_description: take_nullable_val::<String>(&r, "out_partition_description"),
+                    table_rows: take_val::<u64>(&r, "out_table_rows")?,
+                    data_length: take_val::<u64>(&r, "out_data_length")?,
+                    index_length: take_val::<u64>(&r, "out_index_length")?,
+                },
+            )))
+            .collect()
+    })?;
+
+    let (method, expression, subpartition_method, subpartition_expression, _) = match rows.first() {
+        Some(row) => row.clone(),
+        None => return Ok(None),
+    };
+
+    Ok(Some(Partitioning {
+        method: method.unwrap_or_default(),
+        expression,
+        subpartition_method,
+        subpartition_expression,
+        partitions: rows.into_iter().map(|r| r.4).collect(),
+        current_max: None,
+        max_error: None,
+        warning: None,
+    }))
+}
+
+type PartitionRow = (Option<String>, Option<String>, Option<String>, Option<String>, Partition);
+
+pub fn query_partition_max(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+    expression: &str,
+    partition_name: &str,
+) -> mysql::Result<Option<String>> {
+    let query = format!(r#"
+        SELECT
+            CAST(MAX({expr}) AS CHAR) AS out_max
+        FROM
+            {db}.{table} PARTITION ({partition})
+    "#,
+        expr = expression,
+        db = quote_identifier(db_name),
+        table = quote_identifier(table_name),
+        partition = quote_identifier(partition_name),
+    );
+
+    conn.query(query)
+        .and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+        .map::<Option<String>, _>(|rows| {
+            rows.into_iter()
+                .filter_map(|r| take_nullable_val::<String>(&r, "out_max"))
+                .next()
+        })
+}
+
+pub fn query_check_constraints(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+) -> mysql::Result<Vec<CheckConstraint>> {
+    // check_constraints only exists since MySQL 8.0.16, so older servers simply have none.
+    // Any other error is passed on, so it is not mistaken for a table without checks.
+    conn.prep_exec(r#"
+        SELECT
+            tc.constraint_name        AS out_constraint_name,
+            cc.check_clause           AS out_check_clause,
+            tc.enforced               AS out_enforced
+        FROM
+            information_schema.table_constraints tc
+        INNER JOIN
+            information_schema.check_constraints cc
+            ON  cc.constraint_schema  = tc.constraint_schema
+            AND cc.constraint_name    = tc.constraint_name
+        WHERE
+            tc.table_schema     = :in_db_name
+        AND tc.table_name       = :in_table_name
+        AND tc.constraint_type  = 'CHECK'
+        ORDER BY
+            tc.constraint_name
+    "#, params!{
+        "in_db_name" => db_name,
+        "in_table_name" => table_name,
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<CheckConstraint>, _>(|rows| {
+        rows.into_iter()
+            .map(|r| Ok(CheckConstraint {
+                constraint_name: take_val::<String>(&r, "out_constraint_name")?,
+                check_clause: take_val::<String>(&r, "out_check_clause")?,
+                enforced: take_val::<String>(&r, "out_enforced")? == "YES",
+                column_names: vec![],
+            }))
+            .collect()
+    }).or_else(|e| if has_code(&e, ER_UNKNOWN_TABLE) || has_code(&e, ER_NO_SUCH_TABLE) {
+        Ok(vec![])
+    } else {
+        Err(e)
+    })
+}
+
+pub fn query_create_table(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+) -> mysql::Result<Option<String>> {
+    query_show_create(conn, "TABLE", db_name, table_name)
+}
+
+pub fn query_create_view(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    view_name: &str,
+) -> mysql::Result<Option<String>> {
+    query_show_create(conn, "VIEW", db_name, view_name)
+}
+
+fn is_missing_object(error: &mysql::Error) -> bool {
+    has_code(error, ER_NO_SUCH_TABLE) || has_code(error, ER_WRONG_OBJECT)
+}
+
+fn query_show_create(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    object_type: &str,
+    db_name: &str,
+    name: &str,
+) -> mysql::Result<Option<String>> {
+    let query = format!(
+        "SHOW CREATE {} {}.{}",
+        object_type,
+        quote_identifier(db_name),
+        quote_identifier(name),
+    );
+
+    conn.query(query)
+        .and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+        .map::<Option<String>, _>(|rows| {
+            rows.into_iter()
+                .filter_map(|r| r.get_opt::<String, usize>(1).and_then(|v| v.ok()))
+                .next()
+        })
+        // Only a missing object, or a table asked for as a view, means there is no DDL;
+        // timeouts and privilege errors are passed on.
+        .or_else(|e| if is_missing_object(&e) {
+            Ok(None)
+        } else {
+            Err(e)
+        })
+}
+
+pub fn query_exact_row_count(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+    timeout_ms: u64,
+) -> mysql::Result<u64> {
+    let query = format!(r#"
+        SELECT /*+ MAX_EXECUTION_TIME({timeout}) */
+            COUNT(*) AS out_count
+        FROM
+            {db}.{table}
+    "#,
+        timeout = timeout_ms,
+        db = quote_identifier(db_name),
+        table = quote_identifier(table_name),
+    );
+
+    conn.query(query)
+        .and_then(|mut query_result| {
+            match query_result.next() {
+                Some(row) => row.and_then(|r| take_val::<u64>(&r, "out_count")),
+                _ => Ok(0),
+            }
+        })
+}
+
+pub fn query_value_containment(
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    relation: &InferredRelation,
+    sample_size: u32,
+) -> mysql::Result<Option<Containment>> {
+    let query = format!(r#"
+        SELECT
+            COUNT(*)                AS out_sampled,
+            COALESCE(SUM(EXISTS (
+                SELECT
+                    1
+                FROM
+                    {db}.{parent} p
+                WHERE
+                    p.{parent_col} = s.v
+            )), 0)                  AS out_matched
+        FROM
+            (
+                SELECT DISTINCT
+                    c.{child_col} AS v
+                FROM
+                    {db}.{child} c
+                WHERE
+                    c.{child_col} IS NOT NULL
+                LIMIT {limit}
+            ) s
+    "#,
+        db = quote_identifier(db_name),
+        parent = quote_identifier(&relation.referenced_table_name),
+        parent_col = quote_identifier(&relation.referenced_column_name),
+        child = quote_identifier(&relation.table_name),
+        child_col = quote_identifier(&relation.column_name),
+        limit = sample_size,
+    );
+
+    conn.query(query)
+        .and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+        .and_then::<Option<Containment>, _>(|rows| {
+            rows.into_iter()
+                .map(|r| Ok(Containment {
+                    sampled: take_val::<u64>(&r, "out_sampled")?,
+                    matched: take_val::<u64>(&r, "out_matched")?,
+                }))
+                .next()
+                .transpose()
+        })
+}
+
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
 }
 
 fn query_flat_table(
     conn: &mut PooledConnection<MysqlConnectionManager>,
-    db_name: &String,
-    table_name: &String,
-) -> Vec<FlatTable> {
+    db_name: &str,
+    table_name: &str,
+) -> mysql::Result<Vec<FlatTable>> {
     conn.prep_exec(r#"
         SELECT
             tbl.table_name                AS out_table_name,
@@ -183,8 +1083,18 @@ fn query_flat_table(
                 col.column_name
             )                             AS out_column_fqn,
             col.column_type               AS out_column_sql_type,
-            col.is_nullable               AS out_column_nullable,
-            col.column_default            AS out_column_default
+            (col.is_nullable = 'YES')     AS out_column_nullable,
+            col.column_default            AS out_column_default,
+            col.column_key                AS out_column_key,
+            col.extra                     AS out_column_extra,
+            col.character_set_name        AS out_column_character_set_name,
+            col.collation_name            AS out_column_collation_name,
+            col.numeric_precision         AS out_column_numeric_precision,
+            col.numeric_scale             AS out_column_numeric_scale,
+            NULLIF(
+                col.generation_expression, ''
+            )                             AS out_column_generation_expression,
+            col.privileges                AS out_column_privileges
         FROM
             information_schema.tables tbl
         INNER JOIN
@@ -200,32 +1110,40 @@ fn query_flat_table(
     "#, params!{
         "in_db_name" => db_name,
         "in_table_name" => table_name,
-    }).map::<Vec<FlatTable>, _>(|query_result| {
-        query_result
-            .map(|result| result.unwrap())
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<FlatTable>, _>(|rows| {
+        rows.into_iter()
             .map(|r| {
-                FlatTable {
-                    table_name: take_val::<String>(&r, "out_table_name"),
+                Ok(FlatTable {
+                    table_name: take_val::<String>(&r, "out_table_name")?,
                     table_comment: take_nullable_val::<String>(&r, "out_table_comment"),
-                    table_fqn: take_val::<String>(&r, "out_table_fqn"),
-                    table_rows: take_val::<u64>(&r, "out_table_rows"),
-                    column_name: take_val::<String>(&r, "out_column_name"),
+                    table_fqn: take_val::<String>(&r, "out_table_fqn")?,
+                    table_rows: take_val::<u64>(&r, "out_table_rows")?,
+                    column_name: take_val::<String>(&r, "out_column_name")?,
                     column_comment: take_nullable_val::<String>(&r, "out_column_comment"),
-                    column_fqn: take_val::<String>(&r, "out_column_fqn"),
-                    column_sql_type: take_val::<String>(&r, "out_column_sql_type"),
-                    column_nullable: take_val::<String>(&r, "out_column_nullable"),
+                    column_fqn: take_val::<String>(&r, "out_column_fqn")?,
+                    column_sql_type: take_val::<String>(&r, "out_column_sql_type")?,
+                    column_nullable: take_val::<bool>(&r, "out_column_nullable")?,
                     column_default: take_nullable_val::<String>(&r, "out_column_default"),
-                }
+                    column_key: take_val::<String>(&r, "out_column_key")?,
+                    column_extra: take_val::<String>(&r, "out_column_extra")?,
+                    column_character_set_name: take_nullable_val::<String>(&r, "out_column_character_set_name"),
+                    column_collation_name: take_nullable_val::<String>(&r, "out_column_collation_name"),
+                    column_numeric_precision: take_nullable_val::<u64>(&r, "out_column_numeric_precision"),
+                    column_numeric_scale: take_nullable_val::<u64>(&r, "out_column_numeric_scale"),
+                    column_generation_expression: take_nullable_val::<String>(&r, "out_column_generation_expression"),
+                    column_privileges: take_val::<String>(&r, "out_column_privileges")?,
+                })
             })
             .collect()
-    }).unwrap()
+    })
 }
 
 fn query_column_parent(
     conn: &mut PooledConnection<MysqlConnectionManager>,
-    db_name: &String,
-    table_name: &String,
-    column_name: &String,
+    db_name: &str,
+    table_name: &str,
+    column_name: &str,
 ) -> Option<Relation> {
     let parent_result = conn.prep_exec(r#"
         SELECT
@@ -244,9 +1162,9 @@ fn query_column_parent(
         "in_db_name" => db_name,
         "in_table_name" => table_name,
         "in_column_name" => column_name,
-    }).map::<Option<Relation>, _>(|query_result| {
-        let rel: Vec<Option<Relation>> = query_result
-            .map(|result| result.unwrap())
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .map::<Option<Relation>, _>(|rows| {
+        let rel: Vec<Option<Relation>> = rows.into_iter()
             .map(|r| {
                 let parent_table_name = take_nullable_val::<String>(&r, "out_parent_table_name");
                 let parent_column_name = take_nullable_val::<String>(&r, "out_parent_column_name");
@@ -255,6 +1173,7 @@ fn query_column_parent(
                     (Some(t), Some(c)) => Some(Relation {
                         table_name: t,
                         column_name: c,
+                        cardinality: None,
                     }),
                     _ => None,
                 }
@@ -274,9 +1193,9 @@ fn query_column_parent(
 
 fn query_column_children(
     conn: &mut PooledConnection<MysqlConnectionManager>,
-    db_name: &String,
-    table_name: &String,
-    column_name: &String,
+    db_name: &str,
+    table_name: &str,
+    column_name: &str,
 ) -> Vec<Relation> {
     let children_result = conn.prep_exec(r#"
         SELECT
@@ -295,14 +1214,15 @@ fn query_column_children(
         "in_db_name" => db_name,
         "in_table_name" => table_name,
         "in_column_name" => column_name,
-    }).map::<Vec<Relation>, _>(|query_result| {
-        query_result
-            .map(|result| result.unwrap())
+    }).and_then(|query_result| query_result.collect::<mysql::Result<Vec<Row>>>())
+    .and_then::<Vec<Relation>, _>(|rows| {
+        rows.into_iter()
             .map(|r| {
-                Relation {
-                    table_name: take_val::<String>(&r, "out_child_table_name"),
-                    column_name: take_val::<String>(&r, "out_child_column_name")
-                }
+                Ok(Relation {
+                    table_name: take_val::<String>(&r, "out_child_table_name")?,
+                    column_name: take_val::<String>(&r, "out_child_column_name")?,
+                    cardinality: None,
+                })
             }).collect()
     });
 
@@ -312,54 +1232,118 @@ fn query_column_children(
     }
 }
 
+// With `partition_max` the last range partition is read to find how full it is.
 pub fn query_table(
-    mut conn: PooledConnection<MysqlConnectionManager>,
-    db_name: String,
-    table_name: String,
-) -> Option<Table> {
-    let flat_tables = query_flat_table(&mut conn, &db_name, &table_name);
+    conn: &mut PooledConnection<MysqlConnectionManager>,
+    db_name: &str,
+    table_name: &str,
+    partition_max: bool,
+) -> mysql::Result<Option<Table>> {
+    let flat_tables = query_flat_table(conn, db_name, table_name)?;
 
     let first = match flat_tables.first() {
         Some(t) => t.clone(),
-        _ => return None,
+        _ => return Ok(None),
+    };
+
+    let indexes = query_indexes(conn, db_name, Some(table_name))?;
+    let size = query_table_size(conn, db_name, table_name)?;
+    let triggers = query_triggers(conn, db_name, Some(table_name))?;
+    let ddl = query_create_table(conn, db_name, table_name)?;
+    let column_names: Vec<String> = flat_tables.iter().map(|f| f.column_name.clone()).collect();
+    // A failed lookup is shown on the page rather than rendered as a table without checks.
+    let (check_constraints, check_constraints_error) = match query_check_constraints(conn, db_name, table_name) {
+        Ok(checks) => (checks, None),
+        Err(e) => (vec![], Some(e.to_string())),
     };
+    let check_constraints: Vec<CheckConstraint> = check_constraints.into_iter()
+        .map(|mut c| {
+            c.column_names = domain::mentioned_columns(&c.check_clause, &column_names);
+            c
+        })
+        .collect();
+    let partitioning = query_partitioning(conn, db_name, table_name)?
+        .map(|mut p| {
+            if let (true, Some(expr), Some(last)) =
+                (partition_max, partition::max_expression(&p), partition::last_bounded_range(&p)) {
+                // A failed lookup is shown on the panel rather than passing for an empty partition.
+                match query_partition_max(conn, db_name, table_name, expr, &last.partition_name) {
+                    Ok(max) => p.current_max = max,
+                    Err(e) => p.max_error = Some(e.to_string()),
+                }
+            }
+            p.warning = partition::exhaustion_warning(&p);
+            p
+        });
 
-    Some(Table {
+    Ok(Some(Table {
         table_name: first.table_name,
         table_comment: first.table_comment,
         table_fqn: first.table_fqn,
         table_rows: first.table_rows,
+        table_size: size,
+        table_junction: None,
+        table_views: vec![],
+        table_triggers: triggers,
+        table_partitioning: partitioning,
+        table_check_constraints: check_constraints.clone(),
+        table_check_constraints_error: check_constraints_error,
+        table_ddl: ddl,
+        table_indexes: indexes.clone(),
         table_columns: flat_tables.iter().map(|flat| {
             let f = flat.clone();
             let column_name = f.column_name;
-            let parent = query_column_parent(&mut conn, &db_name, &table_name, &column_name);
-            let children = query_column_children(&mut conn, &db_name, &table_name, &column_name);
+            let parent = query_column_parent(conn, db_name, table_name, &column_name);
+            let children = query_column_children(conn, db_name, table_name, &column_name);
+            let in_index = |index: &Index| index.columns.iter()
+                .any(|c| c.column_name.as_ref() == Some(&column_name));
+            let primary_key = indexes.iter().filter(|i| i.primary).any(in_index);
+            let indexed = indexes.iter().any(in_index);
             Column {
+                column_enum_values: domain::enum_values(&f.column_sql_type),
+                column_check_constraints: check_constraints.iter()
+                    .filter(|c| c.column_names.contains(&column_name))
+                    .map(|c| c.constraint_name.clone())
+                    .collect(),
+                column_primary_key: primary_key,
+                column_indexed: indexed,
                 column_name,
                 column_comment: f.column_comment,
                 column_fqn: f.column_fqn,
                 column_sql_type: f.column_sql_type,
                 column_nullable: f.column_nullable,
                 column_default: f.column_default,
+                column_key: f.column_key,
+                column_extra: f.column_extra,
+                column_character_set_name: f.column_character_set_name,
+                column_collation_name: f.column_collation_name,
+                column_numeric_precision: f.column_numeric_precision,
+                column_numeric_scale: f.column_numeric_scale,
+                column_generation_expression: f.column_generation_expression,
+                column_privileges: f.column_privileges
+                    .split(',')
+                    .filter(|p| !p.is_empty())
+                    .map(String::from)
+                    .collect(),
                 column_parent: parent,
                 column_children: children,
+                column_inferred_parents: vec![],
+                column_inferred_children: vec![],
             }
         }).collect(),
-    })
+    }))
 }
 
-fn take_val<T>(row: &Row, index: &str) -> T where T: FromValue {
-    row.get::<T, &str>(index).unwrap()
+fn take_val<T>(row: &Row, index: &str) -> mysql::Result<T> where T: FromValue {
+    match row.get_opt::<T, &str>(index) {
+        Some(v) => Ok(v?),
+        _ => Err(mysql::Error::FromRowError(row.clone())),
+    }
 }
 
 fn take_nullable_val<T>(row: &Row, index: &str) -> Option<T> where T: FromValue {
     match row.get_opt::<T, &str>(index) {
-        Some(a) => {
-            match a {
-                Ok(b) => Some(b),
-                _ => None,
-            }
-        },
+        Some(Ok(v)) => Some(v),
         _ => None,
     }
 }
@@ -375,6 +1359,15 @@ pub struct Table {
     pub table_comment: Option<String>,
     pub table_fqn: String,
     pub table_rows: u64,
+    pub table_size: Option<TableSizeResult>,
+    pub table_junction: Option<Junction>,
+    pub table_views: Vec<String>,
+    pub table_triggers: Vec<Trigger>,
+    pub table_partitioning: Option<Partitioning>,
+    pub table_check_constraints: Vec<CheckConstraint>,
+    pub table_check_constraints_error: Option<String>,
+    pub table_ddl: Option<String>,
+    pub table_indexes: Vec<Index>,
     pub table_columns: Vec<Column>,
 }
 
@@ -388,8 +1381,16 @@ pub struct FlatTable {
     pub column_comment: Option<String>,
     pub column_fqn: String,
     pub column_sql_type: String,
-    pub column_nullable: String,
+    pub column_nullable: bool,
     pub column_default: Option<String>,
+    pub column_key: String,
+    pub column_extra: String,
+    pub column_character_set_name: Option<String>,
+    pub column_collation_name: Option<String>,
+    pub column_numeric_precision: Option<u64>,
+    pub column_numeric_scale: Option<u64>,
+    pub column_generation_expression: Option<String>,
+    pub column_privileges: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -398,27 +1399,231 @@ pub struct Column {
     pub column_comment: Option<String>,
     pub column_fqn: String,
     pub column_sql_type: String,
-    pub column_nullable: String,
+    pub column_nullable: bool,
     pub column_default: Option<String>,
+    pub column_key: String,
+    pub column_extra: String,
+    pub column_character_set_name: Option<String>,
+    pub column_collation_name: Option<String>,
+    pub column_numeric_precision: Option<u64>,
+    pub column_numeric_scale: Option<u64>,
+    pub column_generation_expression: Option<String>,
+    pub column_privileges: Vec<String>,
+    pub column_enum_values: Vec<String>,
+    pub column_check_constraints: Vec<String>,
+    pub column_primary_key: bool,
+    pub column_indexed: bool,
     pub column_parent: Option<Relation>,
     pub column_children: Vec<Relation>,
+    pub column_inferred_parents: Vec<InferredRelation>,
+    pub column_inferred_children: Vec<InferredRelation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct View {
+    pub view_name: String,
+    pub view_definition: String,
+    pub check_option: String,
+    pub is_updatable: bool,
+    pub definer: String,
+    pub security_type: String,
+    pub view_comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Routine {
+    pub routine_name: String,
+    pub routine_type: String,
+    pub return_type: Option<String>,
+    pub routine_definition: Option<String>,
+    pub is_deterministic: bool,
+    pub sql_data_access: String,
+    pub security_type: String,
+    pub definer: String,
+    pub routine_comment: Option<String>,
+    pub created: Option<String>,
+    pub last_altered: Option<String>,
+    pub parameters: Vec<RoutineParameter>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct RoutineParameter {
+    pub parameter_mode: Option<String>,
+    pub parameter_name: String,
+    pub parameter_type: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Trigger {
+    pub trigger_name: String,
+    pub table_name: String,
+    pub action_timing: String,
+    pub event_manipulation: String,
+    pub action_order: u32,
+    pub action_orientation: String,
+    pub action_statement: String,
+    pub definer: String,
+    pub created: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Event {
+    pub event_name: String,
+    pub event_type: String,
+    pub execute_at: Option<String>,
+    pub interval_value: Option<String>,
+    pub interval_field: Option<String>,
+    pub starts: Option<String>,
+    pub ends: Option<String>,
+    pub status: String,
+    pub on_completion: String,
+    pub event_definition: String,
+    pub definer: String,
+    pub last_executed: Option<String>,
+    pub event_comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Partitioning {
+    pub method: String,
+    pub expression: Option<String>,
+    pub subpartition_method: Option<String>,
+    pub subpartition_expression: Option<String>,
+    pub partitions: Vec<Partition>,
+    pub current_max: Option<String>,
+    pub max_error: Option<String>,
+    pub warning: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Partition {
+    pub partition_name: String,
+    pub partition_description: Option<String>,
+    pub table_rows: u64,
+    pub data_length: u64,
+    pub index_length: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CheckConstraint {
+    pub constraint_name: String,
+    pub check_clause: String,
+    pub enforced: bool,
+    pub column_names: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Index {
+    pub table_name: String,
+    pub index_name: String,
+    pub primary: bool,
+    pub unique: bool,
+    pub index_type: String,
+    pub visible: bool,
+    pub cardinality: Option<u64>,
+    pub columns: Vec<IndexColumn>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct IndexColumn {
+    pub column_name: Option<String>,
+    pub sub_part: Option<u32>,
+    pub cardinality: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Relation {
     pub table_name: String,
     pub column_name: String,
+    pub cardinality: Option<Cardinality>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ForeignKey {
+    pub constraint_name: String,
+    pub table_name: String,
+    pub column_names: Vec<String>,
+    pub referenced_table_name: String,
+    pub referenced_column_names: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
+pub struct SchemaColumn {
+    pub table_name: String,
+    pub column_name: String,
+    pub data_type: String,
+    pub column_type: String,
+    pub column_key: String,
+    pub nullable: bool,
+    pub column_default: Option<String>,
+    pub extra: String,
+    pub character_set_name: Option<String>,
+    pub collation_name: Option<String>,
+    pub generation_expression: Option<String>,
+    pub column_comment: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
+pub struct UniqueKey {
+    pub table_name: String,
+    pub index_name: String,
+    pub column_names: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
 pub struct TableOutlineResult {
     pub table_name: String,
     pub table_comment: Option<String>,
     pub table_fqn: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
 pub struct TableSizeResult {
     pub columns: u32,
     pub rows: u64,
+    pub engine: Option<String>,
+    pub row_format: Option<String>,
+    pub data_length: u64,
+    pub index_length: u64,
+    pub data_free: u64,
+    pub auto_increment: Option<u64>,
+    pub table_collation: Option<String>,
+    pub create_options: Option<String>,
+    pub create_time: Option<String>,
+    pub update_time: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_error(code: u16) -> mysql::Error {
+        mysql::Error::MySqlError(mysql::MySqlError {
+            state: String::from("HY000"),
+            message: String::new(),
+            code,
+        })
+    }
+
+    #[test]
+    fn only_the_given_server_error_matches() {
+        assert!(has_code(&server_error(ER_BAD_FIELD_ERROR), ER_BAD_FIELD_ERROR));
+        assert!(!has_code(&server_error(ER_QUERY_TIMEOUT), ER_BAD_FIELD_ERROR));
+        assert!(!has_code(&mysql::Error::DriverError(mysql::DriverError::ConnectTimeout), ER_BAD_FIELD_ERROR));
+    }
+
+    #[test]
+    fn only_missing_objects_have_no_ddl() {
+        assert!(is_missing_object(&server_error(ER_NO_SUCH_TABLE)));
+        assert!(is_missing_object(&server_error(ER_WRONG_OBJECT)));
+        assert!(!is_missing_object(&server_error(ER_QUERY_INTERRUPTED)));
+        assert!(!is_missing_object(&server_error(1142)));
+    }
+
+    #[test]
+    fn timeouts_and_kills_are_timeouts() {
+        assert!(is_timeout(&server_error(ER_QUERY_TIMEOUT)));
+        assert!(is_timeout(&server_error(ER_QUERY_INTERRUPTED)));
+        assert!(!is_timeout(&server_error(ER_BAD_FIELD_ERROR)));
+    }
 }
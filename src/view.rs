@@ -0,0 +1,259 @@
+use std::collections::{
+    BTreeSet,
+    HashMap,
+};
+
+use crate::sql::View;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ViewDependencies {
+    pub table_names: Vec<String>,
+    pub columns: Vec<ViewColumnRef>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct ViewColumnRef {
+    pub table_name: String,
+    pub column_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ViewRead {
+    pub name: String,
+    pub is_view: bool,
+    pub column_names: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    Name(Vec<String>),
+    Word(String),
+    Other,
+}
+
+pub fn dependencies(db_name: &str, definition: &str) -> ViewDependencies {
+    let tokens = tokenize(definition);
+
+    let mut table_names: BTreeSet<String> = BTreeSet::new();
+    let mut aliases: HashMap<String, String> = HashMap::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if let Token::Name(parts) = token {
+            if parts.len() != 2 || parts[0] != db_name {
+                continue;
+            }
+            table_names.insert(parts[1].clone());
+
+            let alias = match (tokens.get(i + 1), tokens.get(i + 2)) {
+                (Some(Token::Word(w)), Some(Token::Name(a))) if w == "as" && a.len() == 1 => Some(&a[0]),
+                (Some(Token::Name(a)), _) if a.len() == 1 => Some(&a[0]),
+                _ => None,
+            };
+            if let Some(a) = alias {
+                aliases.insert(a.clone(), parts[1].clone());
+            }
+        }
+    }
+
+    let mut columns: BTreeSet<ViewColumnRef> = BTreeSet::new();
+
+    for token in tokens.iter() {
+        if let Token::Name(parts) = token {
+            let (table, column) = match parts.len() {
+                3 if parts[0] == db_name => (&parts[1], &parts[2]),
+                2 if parts[0] != db_name => (&parts[0], &parts[1]),
+                _ => continue,
+            };
+            let table_name = match aliases.get(table) {
+                Some(t) => t,
+                _ if table_names.contains(table) => table,
+                _ => continue,
+            };
+            columns.insert(ViewColumnRef {
+                table_name: table_name.clone(),
+                column_name: column.clone(),
+            });
+        }
+    }
+
+    ViewDependencies {
+        table_names: table_names.into_iter().collect(),
+        columns: columns.into_iter().collect(),
+    }
+}
+
+pub fn reads(dependencies: &ViewDependencies, view_names: &[String]) -> Vec<ViewRead> {
+    dependencies.table_names.iter().map(|name| ViewRead {
+        name: name.clone(),
+        is_view: view_names.contains(name),
+        column_names: dependencies.columns.iter()
+            .filter(|c| &c.table_name == name)
+            .map(|c| c.column_name.clone())
+            .collect(),
+    }).collect()
+}
+
+pub fn views_reading<'a>(db_name: &str, views: &'a [View], table_name: &str) -> Vec<&'a View> {
+    views.iter()
+        .filter(|v| dependencies(db_name, &v.view_definition).table_names.iter().any(|t| t == table_name))
+        .collect()
+}
+
+fn tokenize(definition: &str) -> Vec<Token> {
+    let chars: Vec<char> = definition.chars().collect();
+    let mut tokens: Vec<Token> = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '`' {
+            let mut parts: Vec<String> = vec![];
+            loop {
+                let (ident, next) = quoted(&chars, i, '`');
+                parts.push(ident);
+                i = next;
+                if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1] == '`' {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Name(parts));
+        } else if c == '\'' || c == '"' {
+            let (_, next) = quoted(&chars, i, c);
+            i = next;
+            tokens.push(Token::Other);
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect::<String>().to_lowercase()));
+        } else {
+            if !c.is_whitespace() {
+                tokens.push(Token::Other);
+            }
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+fn quoted(chars: &[char], start: usize, quote: char) -> (String, usize) {
+    let mut value = String::new();
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        if chars[i] == quote {
+            if i + 1 < chars.len() && chars[i + 1] == quote {
+                value.push(quote);
+                i += 2;
+                continue;
+            }
+            return (value, i + 1);
+        }
+        if chars[i] == '\\' && quote != '`' && i + 1 < chars.len() {
+            value.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        value.push(chars[i]);
+        i += 1;
+    }
+
+    (value, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(parts: &[&str]) -> Token {
+        Token::Name(parts.iter().map(|p| p.to_string()).collect())
+    }
+
+    fn column(table_name: &str, column_name: &str) -> ViewColumnRef {
+        ViewColumnRef {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn tokenizer_reads_qualified_and_escaped_names() {
+        let tokens = tokenize("select `shop`.`o``k`.`id`, 'it''s `x`' AS \"a\\\"b\" from `shop`.`orders`");
+
+        assert_eq!(tokens, vec![
+            Token::Word(String::from("select")),
+            names(&["shop", "o`k", "id"]),
+            Token::Other,
+            Token::Other,
+            Token::Word(String::from("as")),
+            Token::Other,
+            Token::Word(String::from("from")),
+            names(&["shop", "orders"]),
+        ]);
+    }
+
+    #[test]
+    fn resolves_aliases_to_tables() {
+        let definition = "select `o`.`id` AS `id`,`c`.`name` AS `name` \
+            from (`shop`.`orders` `o` join `shop`.`customers` as `c` on((`o`.`customer_id` = `c`.`id`)))";
+
+        let dependencies = dependencies("shop", definition);
+
+        assert_eq!(dependencies.table_names, vec!["customers", "orders"]);
+        assert_eq!(dependencies.columns, vec![
+            column("customers", "id"),
+            column("customers", "name"),
+            column("orders", "customer_id"),
+            column("orders", "id"),
+        ]);
+    }
+
+    #[test]
+    fn columns_qualified_by_table_or_schema_resolve_without_alias() {
+        let definition = "select `shop`.`orders`.`id` AS `id`,`orders`.`total` AS `total` \
+            from `shop`.`orders` where `other`.`x` = 1";
+
+        let dependencies = dependencies("shop", definition);
+
+        assert_eq!(dependencies.table_names, vec!["orders"]);
+        assert_eq!(dependencies.columns, vec![column("orders", "id"), column("orders", "total")]);
+    }
+
+    #[test]
+    fn tables_of_other_schemas_and_strings_are_ignored() {
+        let definition = "select 'from `shop`.`secrets`' AS `s` from `audit`.`log` join `shop`.`orders`";
+
+        assert_eq!(dependencies("shop", definition).table_names, vec!["orders"]);
+    }
+
+    #[test]
+    fn reads_mark_views_and_views_reading_finds_dependents() {
+        let view = |name: &str, definition: &str| View {
+            view_name: name.to_string(),
+            view_definition: definition.to_string(),
+            check_option: String::from("NONE"),
+            is_updatable: false,
+            definer: String::from("root@%"),
+            security_type: String::from("DEFINER"),
+            view_comment: None,
+        };
+        let views = vec![
+            view("order_totals", "select `o`.`total` AS `total` from `shop`.`orders` `o`"),
+            view("big_orders", "select `t`.`total` AS `total` from `shop`.`order_totals` `t`"),
+        ];
+
+        let reads = reads(&dependencies("shop", &views[1].view_definition), &[String::from("order_totals")]);
+
+        assert_eq!(reads, vec![ViewRead {
+            name: String::from("order_totals"),
+            is_view: true,
+            column_names: vec![String::from("total")],
+        }]);
+        let reading: Vec<&str> = views_reading("shop", &views, "orders").iter().map(|v| v.view_name.as_str()).collect();
+        assert_eq!(reading, vec!["order_totals"]);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use itertools::Itertools;
+use mysql::{
+    Conn,
+    Opts,
+};
+use r2d2::CustomizeConnection;
+
+use crate::sql;
+
+pub const QUERY_TIMEOUT_SECS: &str = "QUERY_TIMEOUT_SECS";
+pub const QUERY_TIMEOUT_SECS_DEFAULT: u64 = 30;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// How long past the timeout a statement may run before KILL QUERY stops it. Statements
+// covered by max_execution_time have been stopped by the server by then.
+const KILL_GRACE_SECS: u64 = 5;
+
+// Server-side ids of the pool's connections, recorded as r2d2 opens them. Ids of closed
+// connections are dropped once they disappear from the processlist.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionIds(Arc<Mutex<HashSet<u64>>>);
+
+impl ConnectionIds {
+    fn snapshot(&self) -> Vec<u64> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn forget(&self, ids: &[u64]) {
+        let mut tracked = self.0.lock().unwrap();
+        for id in ids {
+            tracked.remove(id);
+        }
+    }
+}
+
+// Sets max_execution_time on every connection the pool opens, which makes the server
+// stop read-only SELECTs at the timeout, and records the connection's id for the
+// watchdog. Servers without the variable are left to the watchdog.
+#[derive(Debug, Clone)]
+pub struct SessionTimeout {
+    pub connection_ids: ConnectionIds,
+    pub timeout_secs: u64,
+}
+
+impl CustomizeConnection<Conn, mysql::Error> for SessionTimeout {
+    fn on_acquire(&self, conn: &mut Conn) -> Result<(), mysql::Error> {
+        if self.timeout_secs > 0 {
+            let set = conn.query(format!("SET SESSION max_execution_time = {}", self.timeout_secs * 1000))
+                .map(|_| ());
+            if let Err(e) = set {
+                if !sql::has_code(&e, sql::ER_UNKNOWN_SYSTEM_VARIABLE) {
+                    return Err(e);
+                }
+            }
+        }
+        if let Some(id) = conn.first::<_, u64>("SELECT CONNECTION_ID()")? {
+            self.connection_ids.0.lock().unwrap().insert(id);
+        }
+        Ok(())
+    }
+}
+
+// Runs KILL QUERY on pool connections whose current statement has run for longer than
+// the timeout plus a grace period. It is the backstop for what max_execution_time does
+// not cover: SHOW statements, statements other than SELECT, and servers before 5.7.8.
+// The watchdog uses its own connection, so it keeps working while every
+// pooled connection is stuck. A timeout of 0 disables it.
+pub fn start(opts: Opts, connection_ids: ConnectionIds, timeout_secs: u64) {
+    if timeout_secs == 0 {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut conn: Option<Conn> = None;
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            if conn.is_none() {
+                conn = Conn::new(opts.clone()).ok();
+            }
+            let result = match conn.as_mut() {
+                Some(c) => kill_slow_queries(c, &connection_ids, timeout_secs),
+                None => continue,
+            };
+            if result.is_err() {
+                conn = None;
+            }
+        }
+    });
+}
+
+fn kill_slow_queries(
+    conn: &mut Conn,
+    connection_ids: &ConnectionIds,
+    timeout_secs: u64,
+) -> mysql::Result<()> {
+    let tracked = connection_ids.snapshot();
+    if tracked.is_empty() {
+        return Ok(());
+    }
+
+    let processes: Vec<Process> = conn.query(format!(r#"
+        SELECT
+            prc.id            AS out_id,
+            prc.command       AS out_command,
+            prc.time          AS out_time,
+            prc.info          AS out_info
+        FROM
+            information_schema.processlist prc
+        WHERE
+            prc.id IN ({ids})
+    "#, ids = tracked.iter().join(", ")))
+        .and_then(|result| result.map(|row| row.map(mysql::from_row)).collect())?;
+
+    let closed: Vec<u64> = tracked.into_iter()
+        .filter(|id| !processes.iter().any(|p| p.0 == *id))
+        .collect();
+    connection_ids.forget(&closed);
+
+    for process in processes.iter().filter(|p| is_slow(p, timeout_secs)) {
+        if still_running(conn, process)? {
+            conn.query(format!("KILL QUERY {}", process.0))?;
+        }
+    }
+    Ok(())
+}
+
+type Process = (u64, String, u64, Option<String>);
+
+fn is_slow(process: &Process, timeout_secs: u64) -> bool {
+    process.1 == "Query" && process.2 >= timeout_secs + KILL_GRACE_SECS
+}
+
+// The slow statement may have finished since the processlist was read, and the pooled
+// connection may have started another request's query, which must not be killed. The
+// same statement text with a time that has not restarted is the same statement.
+fn still_running(conn: &mut Conn, process: &Process) -> mysql::Result<bool> {
+    let current: Option<(String, u64, Option<String>)> = conn.first(format!(r#"
+        SELECT
+            prc.command       AS out_command,
+            prc.time          AS out_time,
+            prc.info          AS out_info
+        FROM
+            information_schema.processlist prc
+        WHERE
+            prc.id = {id}
+    "#, id = process.0))?;
+
+    Ok(match current {
+        Some((command, time, info)) => command == process.1 && time >= process.2 && info == process.3,
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(command: &str, time: u64) -> Process {
+        (7, String::from(command), time, Some(String::from("SELECT 1")))
+    }
+
+    #[test]
+    fn only_queries_past_the_grace_period_are_slow() {
+        assert!(!is_slow(&process("Query", 30), 30));
+        assert!(!is_slow(&process("Query", 34), 30));
+        assert!(is_slow(&process("Query", 35), 30));
+        assert!(!is_slow(&process("Sleep", 120), 30));
+    }
+}
@@ -0,0 +1,313 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use rustack::cli;
+use rustack::diff::{
+    self,
+    Change,
+    ChangeKind,
+    ObjectKind,
+};
+use rustack::error::AppError;
+use rustack::snapshot::{
+    Snapshot,
+    SnapshotTable,
+    SNAPSHOT_VERSION,
+};
+use rustack::sql::{
+    CheckConstraint,
+    ForeignKey,
+    Index,
+    IndexColumn,
+    Partition,
+    Partitioning,
+    Routine,
+    SchemaColumn,
+};
+
+fn table(name: &str) -> SnapshotTable {
+    SnapshotTable {
+        table_name: String::from(name),
+        table_comment: None,
+        engine: Some(String::from("InnoDB")),
+        table_collation: Some(String::from("utf8mb4_0900_ai_ci")),
+        create_options: None,
+        check_constraints: vec![],
+        partitioning: None,
+    }
+}
+
+fn column(table_name: &str, name: &str, column_type: &str) -> SchemaColumn {
+    SchemaColumn {
+        table_name: String::from(table_name),
+        column_name: String::from(name),
+        data_type: String::from(column_type.split('(').next().unwrap()),
+        column_type: String::from(column_type),
+        column_key: String::new(),
+        nullable: false,
+        column_default: None,
+        extra: String::new(),
+        character_set_name: None,
+        collation_name: None,
+        generation_expression: None,
+        column_comment: String::new(),
+    }
+}
+
+fn primary_key(table_name: &str) -> Index {
+    Index {
+        table_name: String::from(table_name),
+        index_name: String::from("PRIMARY"),
+        primary: true,
+        unique: true,
+        index_type: String::from("BTREE"),
+        visible: true,
+        cardinality: None,
+        columns: vec![IndexColumn { column_name: Some(String::from("id")), sub_part: None, cardinality: None }],
+    }
+}
+
+fn shop() -> Snapshot {
+    Snapshot {
+        snapshot_version: SNAPSHOT_VERSION,
+        database: String::from("shop"),
+        tables: vec![table("customers"), table("orders")],
+        columns: vec![
+            column("customers", "id", "int(11)"),
+            column("orders", "id", "int(11)"),
+            column("orders", "customer_id", "int(11)"),
+            column("orders", "total", "decimal(10,2)"),
+        ],
+        indexes: vec![primary_key("customers"), primary_key("orders")],
+        foreign_keys: vec![ForeignKey {
+            constraint_name: String::from("fk_orders_customer"),
+            table_name: String::from("orders"),
+            column_names: vec![String::from("customer_id")],
+            referenced_table_name: String::from("customers"),
+            referenced_column_names: vec![String::from("id")],
+        }],
+        views: vec![],
+        routines: vec![],
+        triggers: vec![],
+    }
+}
+
+fn snapshot_file(name: &str, snapshot: &Snapshot) -> PathBuf {
+    let path = env::temp_dir().join(format!("rustack-cli-{}-{}.json", name, std::process::id()));
+    fs::write(&path, serde_json::to_string(snapshot).unwrap()).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Result<i32, AppError> {
+    let matches = cli::app()
+        .get_matches_from_safe(["rustack"].iter().chain(args.iter()))
+        .unwrap();
+    cli::run(&matches, &|_| None)
+}
+
+#[test]
+fn identical_snapshots_have_no_changes() {
+    assert_eq!(diff::diff(&shop(), &shop()), vec![]);
+}
+
+#[test]
+fn diff_reports_added_removed_and_changed_objects() {
+    let before = shop();
+    let mut after = shop();
+    after.tables.push(table("invoices"));
+    after.columns.retain(|c| c.column_name != "customer_id");
+    after.columns.iter_mut()
+        .filter(|c| c.column_name == "total")
+        .for_each(|c| c.column_type = String::from("decimal(12,2)"));
+    after.foreign_keys.clear();
+
+    let changes = diff::diff(&before, &after);
+
+    assert_eq!(changes, vec![
+        Change {
+            change: ChangeKind::Added,
+            object: ObjectKind::Table,
+            name: String::from("invoices"),
+            before: None,
+            after: Some(String::from("TABLE ENGINE=InnoDB COLLATE=utf8mb4_0900_ai_ci")),
+        },
+        Change {
+            change: ChangeKind::Removed,
+            object: ObjectKind::Column,
+            name: String::from("orders.customer_id"),
+            before: Some(String::from("int(11) NOT NULL")),
+            after: None,
+        },
+        Change {
+            change: ChangeKind::Changed,
+            object: ObjectKind::Column,
+            name: String::from("orders.total"),
+            before: Some(String::from("decimal(10,2) NOT NULL")),
+            after: Some(String::from("decimal(12,2) NOT NULL")),
+        },
+        Change {
+            change: ChangeKind::Removed,
+            object: ObjectKind::ForeignKey,
+            name: String::from("orders.fk_orders_customer"),
+            before: Some(String::from("(customer_id) REFERENCES customers (id)")),
+            after: None,
+        },
+    ]);
+}
+
+fn routine(routine_type: &str, name: &str) -> Routine {
+    Routine {
+        routine_name: String::from(name),
+        routine_type: String::from(routine_type),
+        return_type: None,
+        routine_definition: Some(String::from("BEGIN END")),
+        is_deterministic: false,
+        sql_data_access: String::from("CONTAINS SQL"),
+        security_type: String::from("DEFINER"),
+        definer: String::from("root@%"),
+        routine_comment: None,
+        created: None,
+        last_altered: None,
+        parameters: vec![],
+    }
+}
+
+fn changed_names(before: &Snapshot, after: &Snapshot) -> Vec<(ObjectKind, String)> {
+    diff::diff(before, after).into_iter().map(|c| (c.object, c.name)).collect()
+}
+
+#[test]
+fn diff_compares_column_attributes_beyond_the_type() {
+    let before = shop();
+    let mut after = shop();
+    for column in after.columns.iter_mut() {
+        match column.column_name.as_str() {
+            "total" => column.column_default = Some(String::from("0.00")),
+            "customer_id" => column.collation_name = Some(String::from("utf8mb4_bin")),
+            "id" if column.table_name == "orders" => column.extra = String::from("auto_increment"),
+            _ => {},
+        }
+    }
+
+    let changes = diff::diff(&before, &after);
+
+    assert_eq!(changes.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+               vec!["orders.customer_id", "orders.id", "orders.total"]);
+    assert_eq!(changes[1].after.as_deref(), Some("int(11) NOT NULL AUTO_INCREMENT"));
+    assert_eq!(changes[2].after.as_deref(), Some("decimal(10,2) NOT NULL DEFAULT 0.00"));
+}
+
+#[test]
+fn diff_compares_engine_checks_and_partitioning() {
+    let before = shop();
+    let mut after = shop();
+    after.tables[0].engine = Some(String::from("MyISAM"));
+    after.tables[1].check_constraints.push(CheckConstraint {
+        constraint_name: String::from("chk_total"),
+        check_clause: String::from("(`total` >= 0)"),
+        enforced: true,
+        column_names: vec![],
+    });
+    after.tables[1].partitioning = Some(Partitioning {
+        method: String::from("RANGE"),
+        expression: Some(String::from("`id`")),
+        subpartition_method: None,
+        subpartition_expression: None,
+        partitions: vec![Partition {
+            partition_name: String::from("p0"),
+            partition_description: Some(String::from("1000")),
+            table_rows: 0,
+            data_length: 0,
+            index_length: 0,
+        }],
+        current_max: None,
+        max_error: None,
+        warning: None,
+    });
+
+    let changes = diff::diff(&before, &after);
+
+    assert_eq!(changes.iter().map(|c| (c.object, c.name.as_str())).collect::<Vec<_>>(), vec![
+        (ObjectKind::Table, "customers"),
+        (ObjectKind::Table, "orders"),
+        (ObjectKind::CheckConstraint, "orders.chk_total"),
+    ]);
+    assert!(changes[1].after.as_deref().unwrap().ends_with("PARTITION BY RANGE (`id`) (p0 VALUES LESS THAN (1000))"));
+    assert_eq!(changes[2].after.as_deref(), Some("CHECK ((`total` >= 0))"));
+}
+
+#[test]
+fn routines_with_the_same_name_are_told_apart_by_type() {
+    let mut before = shop();
+    before.routines = vec![routine("FUNCTION", "refresh"), routine("PROCEDURE", "refresh")];
+    let mut after = before.clone();
+    after.routines[1].routine_definition = Some(String::from("BEGIN SELECT 1; END"));
+
+    assert_eq!(diff::diff(&before, &before.clone()), vec![]);
+    assert_eq!(changed_names(&before, &after), vec![(ObjectKind::Routine, String::from("PROCEDURE refresh"))]);
+}
+
+#[test]
+fn diff_exits_with_one_when_snapshots_differ() {
+    let mut changed = shop();
+    changed.tables.pop();
+    let before = snapshot_file("before", &shop());
+    let same = snapshot_file("same", &shop());
+    let after = snapshot_file("after", &changed);
+
+    assert_eq!(run(&["diff", before.to_str().unwrap(), same.to_str().unwrap()]).unwrap(), cli::EXIT_OK);
+    assert_eq!(run(&["diff", "--format", "json", before.to_str().unwrap(), after.to_str().unwrap()]).unwrap(), cli::EXIT_FOUND);
+}
+
+#[test]
+fn unreadable_or_foreign_snapshots_have_their_own_exit_codes() {
+    let mut future = shop();
+    future.snapshot_version = SNAPSHOT_VERSION + 1;
+    let future = snapshot_file("future", &future);
+    let garbage = env::temp_dir().join(format!("rustack-cli-garbage-{}.json", std::process::id()));
+    fs::write(&garbage, "{\"tables\": []}").unwrap();
+
+    let missing = run(&["diff", "/nonexistent/before.json", future.to_str().unwrap()]).err().unwrap();
+    assert_eq!(missing.exit_code(), 74);
+
+    let newer = run(&["diff", future.to_str().unwrap(), future.to_str().unwrap()]).err().unwrap();
+    assert_eq!(newer.exit_code(), 65);
+    assert!(newer.to_string().contains("snapshot version"));
+
+    let invalid = run(&["diff", garbage.to_str().unwrap(), future.to_str().unwrap()]).err().unwrap();
+    assert_eq!(invalid.kind(), "input");
+}
+
+#[test]
+fn lint_checks_a_snapshot_without_a_database() {
+    let unindexed = snapshot_file("unindexed", &shop());
+    let mut indexed = shop();
+    indexed.indexes.push(Index {
+        index_name: String::from("idx_customer"),
+        primary: false,
+        unique: false,
+        columns: vec![IndexColumn { column_name: Some(String::from("customer_id")), sub_part: None, cardinality: None }],
+        ..primary_key("orders")
+    });
+    let indexed = snapshot_file("indexed", &indexed);
+
+    assert_eq!(run(&["lint", "--snapshot", unindexed.to_str().unwrap()]).unwrap(), cli::EXIT_FOUND);
+    assert_eq!(run(&["lint", "--format", "json", "--snapshot", indexed.to_str().unwrap()]).unwrap(), cli::EXIT_OK);
+}
+
+#[test]
+fn commands_that_connect_report_configuration_problems() {
+    let error = run(&["snapshot"]).err().unwrap();
+
+    assert_eq!(error.kind(), "config");
+    assert_eq!(error.exit_code(), 78);
+}
+
+#[test]
+fn site_export_needs_an_output_directory() {
+    let error = cli::app().get_matches_from_safe(["rustack", "export", "--format", "site"]).err().unwrap();
+
+    assert!(error.use_stderr());
+    assert!(error.message.contains("--output"));
+}
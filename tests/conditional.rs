@@ -0,0 +1,267 @@
+use std::time::{
+    Duration,
+    UNIX_EPOCH,
+};
+
+use std::collections::HashMap;
+
+use actix_web::{
+    http::header,
+    test,
+};
+
+use rustack::catalog::Catalog;
+use rustack::conditional::{
+    self,
+    PageVersions,
+    Validator,
+    Version,
+};
+use rustack::handler;
+use rustack::search::SearchIndex;
+use rustack::sql::{
+    Partition,
+    Partitioning,
+    Table,
+    TableOutlineResult,
+    Trigger,
+    View,
+};
+
+const LOADED_AT: u64 = 1_500_000_000;
+
+fn version() -> Version {
+    Version::of("catalog", None, LOADED_AT)
+}
+
+fn validator() -> Validator {
+    Validator::new(&version())
+}
+
+fn http_date(epoch: u64) -> String {
+    header::HttpDate::from(UNIX_EPOCH + Duration::from_secs(epoch)).to_string()
+}
+
+#[test]
+fn etag_is_quoted_and_stable() {
+    assert_eq!(conditional::etag("catalog"), conditional::etag("catalog"));
+    assert_ne!(conditional::etag("catalog"), conditional::etag("catalog2"));
+
+    let etag = conditional::etag("catalog");
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    assert_eq!(etag.len(), 18);
+}
+
+#[test]
+fn version_keeps_modified_time_while_content_is_unchanged() {
+    let first = version();
+    let same = Version::of("catalog", Some(&first), LOADED_AT + 60);
+    let changed = Version::of("catalog changed", Some(&first), LOADED_AT + 60);
+
+    assert_eq!(same, first);
+    assert_ne!(changed.etag, first.etag);
+    assert_eq!(changed.modified_epoch, LOADED_AT + 60);
+}
+
+#[test]
+fn with_mixes_in_extra_state() {
+    let base = validator();
+    let mixed = validator().with(&3u64, LOADED_AT + 10);
+    let older = validator().with(&3u64, LOADED_AT - 10);
+
+    assert_ne!(mixed.etag, base.etag);
+    assert_eq!(mixed.etag, validator().with(&3u64, LOADED_AT + 10).etag);
+    assert_ne!(mixed.etag, validator().with(&4u64, LOADED_AT + 10).etag);
+    assert_eq!(mixed.last_modified, UNIX_EPOCH + Duration::from_secs(LOADED_AT + 10));
+    assert_eq!(older.last_modified, base.last_modified);
+}
+
+#[test]
+fn unconditional_request_is_not_fresh() {
+    let req = test::TestRequest::default().to_http_request();
+
+    assert!(!validator().is_fresh(&req));
+}
+
+#[test]
+fn if_none_match_compares_etags() {
+    let etag = validator().etag;
+    let fresh = |value: &str| validator().is_fresh(
+        &test::TestRequest::default().header(header::IF_NONE_MATCH, value).to_http_request());
+
+    assert!(fresh(&etag));
+    assert!(fresh(&format!("W/{}", etag)));
+    assert!(fresh(&format!("\"other\", {}", etag)));
+    assert!(fresh("*"));
+    assert!(!fresh("\"other\""));
+}
+
+#[test]
+fn if_none_match_takes_precedence_over_if_modified_since() {
+    let req = test::TestRequest::default()
+        .header(header::IF_NONE_MATCH, "\"other\"")
+        .header(header::IF_MODIFIED_SINCE, http_date(LOADED_AT + 60))
+        .to_http_request();
+
+    assert!(!validator().is_fresh(&req));
+}
+
+#[test]
+fn if_modified_since_compares_dates() {
+    let fresh = |value: &str| validator().is_fresh(
+        &test::TestRequest::default().header(header::IF_MODIFIED_SINCE, value).to_http_request());
+
+    assert!(fresh(&http_date(LOADED_AT)));
+    assert!(fresh(&http_date(LOADED_AT + 60)));
+    assert!(!fresh(&http_date(LOADED_AT - 60)));
+    assert!(!fresh("not a date"));
+}
+
+fn catalog() -> Catalog {
+    Catalog {
+        fingerprint: String::from("shop"),
+        tables: vec![TableOutlineResult {
+            table_name: String::from("orders"),
+            table_comment: None,
+            table_fqn: String::from("shop.orders"),
+        }],
+        table_sizes: HashMap::new(),
+        parent_counts: HashMap::new(),
+        child_counts: HashMap::new(),
+        columns: vec![],
+        foreign_keys: vec![],
+        unique_keys: vec![],
+        inferred_relations: vec![],
+        search_index: SearchIndex::default(),
+        loaded_at: http_date(LOADED_AT),
+        loaded_at_epoch: LOADED_AT,
+        version: version(),
+    }
+}
+
+fn orders() -> Table {
+    Table {
+        table_name: String::from("orders"),
+        table_comment: None,
+        table_fqn: String::from("shop.orders"),
+        table_rows: 10,
+        table_size: None,
+        table_junction: None,
+        table_views: vec![],
+        table_triggers: vec![],
+        table_partitioning: None,
+        table_check_constraints: vec![],
+        table_check_constraints_error: None,
+        table_ddl: None,
+        table_indexes: vec![],
+        table_columns: vec![],
+    }
+}
+
+fn order_totals() -> View {
+    View {
+        view_name: String::from("order_totals"),
+        view_definition: String::from("select sum(`o`.`total`) AS `total` from `shop`.`orders` `o`"),
+        check_option: String::from("NONE"),
+        is_updatable: false,
+        definer: String::from("root@%"),
+        security_type: String::from("DEFINER"),
+        view_comment: None,
+    }
+}
+
+fn audit_trigger() -> Trigger {
+    Trigger {
+        trigger_name: String::from("orders_audit"),
+        table_name: String::from("orders"),
+        action_timing: String::from("AFTER"),
+        event_manipulation: String::from("INSERT"),
+        action_order: 1,
+        action_orientation: String::from("ROW"),
+        action_statement: String::from("INSERT INTO audit VALUES (NEW.id)"),
+        definer: String::from("root@%"),
+        created: None,
+    }
+}
+
+fn partitioned(current_max: &str) -> Partitioning {
+    let partition = |name: &str, bound: &str| Partition {
+        partition_name: String::from(name),
+        partition_description: Some(String::from(bound)),
+        table_rows: 0,
+        data_length: 0,
+        index_length: 0,
+    };
+    Partitioning {
+        method: String::from("RANGE"),
+        expression: Some(String::from("`id`")),
+        subpartition_method: None,
+        subpartition_expression: None,
+        partitions: vec![partition("p0", "100"), partition("p1", "200")],
+        current_max: Some(String::from(current_max)),
+        max_error: None,
+        warning: Some(format!("current max {}", current_max)),
+    }
+}
+
+#[test]
+fn table_page_etag_changes_with_inputs_outside_the_catalog() {
+    let tera = handler::templates().unwrap();
+    let versions = PageVersions::new();
+    let catalog = catalog();
+    let render = |views: &[View], table: Table, now: u64| {
+        handler::render_table(&tera, "shop", &catalog, views, table, &versions, now).unwrap().0
+    };
+    let if_none_match = |validator: &Validator| test::TestRequest::default()
+        .header(header::IF_NONE_MATCH, validator.etag.as_str())
+        .to_http_request();
+
+    let first = render(&[], orders(), LOADED_AT);
+    let same = render(&[], orders(), LOADED_AT + 60);
+    assert_eq!(same, first);
+    assert!(same.is_fresh(&if_none_match(&first)));
+
+    let mut with_trigger = orders();
+    with_trigger.table_triggers.push(audit_trigger());
+    let trigger_added = render(&[], with_trigger, LOADED_AT + 120);
+    assert_ne!(trigger_added.etag, first.etag);
+    assert_eq!(trigger_added.last_modified, UNIX_EPOCH + Duration::from_secs(LOADED_AT + 120));
+    assert!(!trigger_added.is_fresh(&if_none_match(&first)));
+
+    let view_added = render(&[order_totals()], orders(), LOADED_AT + 180);
+    assert_ne!(view_added.etag, first.etag);
+    assert!(!view_added.is_fresh(&if_none_match(&first)));
+
+    let mut filling = orders();
+    filling.table_partitioning = Some(partitioned("150"));
+    let before_insert = render(&[], filling.clone(), LOADED_AT + 240);
+    filling.table_partitioning = Some(partitioned("195"));
+    let after_insert = render(&[], filling, LOADED_AT + 300);
+    assert_ne!(after_insert.etag, before_insert.etag);
+}
+
+#[test]
+fn partition_max_errors_are_shown_on_the_panel() {
+    let tera = handler::templates().unwrap();
+    let mut table = orders();
+    let mut partitioning = partitioned("150");
+    partitioning.current_max = None;
+    partitioning.warning = None;
+    partitioning.max_error = Some(String::from("Query execution was interrupted"));
+    table.table_partitioning = Some(partitioning);
+
+    let (_, html) = handler::render_table(&tera, "shop", &catalog(), &[], table, &PageVersions::new(), LOADED_AT).unwrap();
+
+    assert!(html.contains("Could not read the current max of the last range partition: Query execution was interrupted"));
+}
+
+#[test]
+fn check_constraint_errors_are_shown_on_the_page() {
+    let tera = handler::templates().unwrap();
+    let mut table = orders();
+    table.table_check_constraints_error = Some(String::from("SELECT command denied"));
+
+    let (_, html) = handler::render_table(&tera, "shop", &catalog(), &[], table, &PageVersions::new(), LOADED_AT).unwrap();
+
+    assert!(html.contains("Could not read the check constraints: SELECT command denied"));
+}
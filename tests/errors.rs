@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use actix_web::{
+    http::{
+        header,
+        StatusCode,
+    },
+    test,
+    web,
+    App,
+    HttpResponse,
+};
+use mysql::{
+    MySqlError,
+    OptsBuilder,
+    Value,
+};
+use r2d2_mysql::MysqlConnectionManager;
+
+use rustack::error::{
+    AppError,
+    JsonError,
+};
+use rustack::sql;
+
+fn server_error(code: u16, message: &str) -> mysql::Error {
+    mysql::Error::MySqlError(MySqlError {
+        state: String::from("HY000"),
+        message: String::from(message),
+        code,
+    })
+}
+
+// A pool pointed at a port nothing listens on, so every checkout fails.
+fn connection_error() -> AppError {
+    let mut builder = OptsBuilder::new();
+    builder.ip_or_hostname(Some("127.0.0.1")).tcp_port(1);
+    let pool = r2d2::Pool::builder()
+        .connection_timeout(Duration::from_millis(200))
+        .build_unchecked(MysqlConnectionManager::new(builder));
+
+    AppError::from(pool.get().err().unwrap())
+}
+
+fn timeout_error() -> AppError {
+    AppError::from(server_error(sql::ER_QUERY_INTERRUPTED, "Query execution was interrupted"))
+}
+
+fn html(error: fn() -> AppError) -> impl Fn() -> Result<HttpResponse, AppError> + Clone {
+    move || Err(error())
+}
+
+fn json(error: fn() -> AppError) -> impl Fn() -> Result<HttpResponse, JsonError> + Clone {
+    move || Err(JsonError(error()))
+}
+
+fn body_text(res: actix_web::dev::ServiceResponse) -> String {
+    String::from_utf8(test::read_body(res).to_vec()).unwrap()
+}
+
+#[test]
+fn connection_failure_is_service_unavailable() {
+    let error = connection_error();
+
+    assert_eq!(error.kind(), "connection");
+    assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[test]
+fn query_failure_is_internal_server_error() {
+    let error = AppError::from(server_error(1146, "Table 'shop.missing' doesn't exist"));
+
+    assert_eq!(error.kind(), "query");
+    assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(error.to_string().contains("shop.missing"));
+}
+
+#[test]
+fn killed_or_expired_queries_are_gateway_timeouts() {
+    let killed = timeout_error();
+    let expired = AppError::from(server_error(sql::ER_QUERY_TIMEOUT, "Query execution was interrupted, maximum statement execution time exceeded"));
+
+    assert_eq!(killed.kind(), "timeout");
+    assert_eq!(killed.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(expired.kind(), "timeout");
+}
+
+#[test]
+fn decode_failure_is_internal_server_error() {
+    let error = AppError::from(mysql::Error::FromValueError(Value::NULL));
+
+    assert_eq!(error.kind(), "decode");
+    assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn template_failure_is_internal_server_error() {
+    let error = AppError::from(tera::Tera::default().render("missing.html", &tera::Context::new()).err().unwrap());
+
+    assert_eq!(error.kind(), "template");
+    assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn html_routes_render_an_error_page() {
+    let mut app = test::init_service(App::new()
+        .route("/connection", web::get().to(html(connection_error)))
+        .route("/timeout", web::get().to(html(timeout_error))));
+
+    let res = test::call_service(&mut app, test::TestRequest::get().uri("/connection").to_request());
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "text/html");
+    assert!(res.headers().contains_key(header::RETRY_AFTER));
+    assert!(body_text(res).contains("<h1 class=\"title is-2\">Database unavailable</h1>"));
+
+    let res = test::call_service(&mut app, test::TestRequest::get().uri("/timeout").to_request());
+    assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+    let body = body_text(res);
+    assert!(body.contains("Query timed out"));
+    assert!(body.contains("cancelled on the server"));
+}
+
+#[test]
+fn error_page_escapes_the_message() {
+    let mut app = test::init_service(App::new()
+        .route("/query", web::get().to(html(|| AppError::from(server_error(1064, "near '<script>'"))))));
+
+    let res = test::call_service(&mut app, test::TestRequest::get().uri("/query").to_request());
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = body_text(res);
+    assert!(!body.contains("<script>"));
+    assert!(body.contains("&lt;script&gt;"));
+}
+
+#[test]
+fn json_routes_return_an_error_body() {
+    let mut app = test::init_service(App::new()
+        .route("/api/connection", web::get().to(json(connection_error)))
+        .route("/api/decode", web::get().to(json(|| AppError::from(mysql::Error::FromValueError(Value::NULL))))));
+
+    let res = test::call_service(&mut app, test::TestRequest::get().uri("/api/connection").to_request());
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "application/json");
+    let body = body_text(res);
+    assert!(body.contains("\"error\":\"connection\""));
+    assert!(body.contains("\"status\":503"));
+
+    let res = test::call_service(&mut app, test::TestRequest::get().uri("/api/decode").to_request());
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body_text(res).contains("\"error\":\"decode\""));
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use rustack::catalog::Catalog;
+use rustack::conditional::Version;
+use rustack::handler;
+use rustack::page;
+use rustack::search::SearchIndex;
+use rustack::settings::Features;
+use rustack::site::{
+    self,
+    Page,
+};
+use rustack::sql::{
+    ForeignKey,
+    SchemaColumn,
+    TableOutlineResult,
+    TableSizeResult,
+    UniqueKey,
+};
+
+fn table(name: &str) -> TableOutlineResult {
+    TableOutlineResult {
+        table_name: String::from(name),
+        table_comment: None,
+        table_fqn: format!("shop.{}", name),
+    }
+}
+
+fn column(table_name: &str, name: &str) -> SchemaColumn {
+    SchemaColumn {
+        table_name: String::from(table_name),
+        column_name: String::from(name),
+        data_type: String::from("int"),
+        column_type: String::from("int(11)"),
+        column_key: String::new(),
+        nullable: false,
+        column_default: None,
+        extra: String::new(),
+        character_set_name: None,
+        collation_name: None,
+        generation_expression: None,
+        column_comment: String::new(),
+    }
+}
+
+fn size() -> TableSizeResult {
+    TableSizeResult {
+        columns: 2,
+        rows: 10,
+        engine: Some(String::from("InnoDB")),
+        row_format: None,
+        data_length: 16384,
+        index_length: 0,
+        data_free: 0,
+        auto_increment: None,
+        table_collation: None,
+        create_options: None,
+        create_time: None,
+        update_time: None,
+    }
+}
+
+fn shop() -> Catalog {
+    let version = Version { etag: String::from("\"shop\""), modified_epoch: 0 };
+    let mut catalog = Catalog {
+        fingerprint: String::from("shop"),
+        tables: vec![table("customers"), table("orders")],
+        table_sizes: vec![(String::from("customers"), size()), (String::from("orders"), size())].into_iter().collect(),
+        parent_counts: vec![(String::from("orders"), 1)].into_iter().collect(),
+        child_counts: vec![(String::from("customers"), 1)].into_iter().collect(),
+        columns: vec![column("customers", "id"), column("orders", "id"), column("orders", "customer_id")],
+        foreign_keys: vec![ForeignKey {
+            constraint_name: String::from("fk_orders_customer"),
+            table_name: String::from("orders"),
+            column_names: vec![String::from("customer_id")],
+            referenced_table_name: String::from("customers"),
+            referenced_column_names: vec![String::from("id")],
+        }],
+        unique_keys: vec![UniqueKey {
+            table_name: String::from("customers"),
+            index_name: String::from("PRIMARY"),
+            column_names: vec![String::from("id")],
+        }],
+        inferred_relations: vec![],
+        search_index: SearchIndex::default(),
+        loaded_at: String::from("Fri, 14 Jul 2017 02:40:00 GMT"),
+        loaded_at_epoch: 1_500_000_000,
+        version,
+    };
+    catalog.derive(None);
+    catalog
+}
+
+fn pages(features: Features) -> HashMap<String, String> {
+    let tera = handler::templates().unwrap();
+    site::catalog_pages(&tera, "shop", &shop(), features).unwrap()
+        .into_iter()
+        .map(|Page { path, contents }| (path, contents))
+        .collect()
+}
+
+#[test]
+fn urls_map_to_files() {
+    assert_eq!(site::file_path("/tables"), "tables.html");
+    assert_eq!(site::file_path("/table/orders"), "table/orders.html");
+    assert_eq!(site::file_path("/table/orders/ddl"), "table/orders.sql");
+    assert_eq!(site::file_path("/ddl"), "schema.sql");
+    assert_eq!(site::file_path("/diagram.mmd"), "diagram.mmd");
+}
+
+#[test]
+fn links_are_relative_to_the_page() {
+    let html = concat!(
+        r#"<link rel="stylesheet" href="https://cdn.example/bulma.css">"#,
+        r#"<script src="//cdn.example/all.js"></script>"#,
+        r#"<a href="/table/customers">customers</a>"#,
+        r#"<a href="/triggers#trigger-audit">audit</a>"#,
+        r#"<a href="/relations/inferred?sample=true">sampled</a>"#,
+        r##"<a href="#columns">columns</a>"##,
+    );
+
+    assert_eq!(site::relative_links(html, 1), concat!(
+        r#"<link rel="stylesheet" href="https://cdn.example/bulma.css">"#,
+        r#"<script src="//cdn.example/all.js"></script>"#,
+        r#"<a href="../table/customers.html">customers</a>"#,
+        r#"<a href="../triggers.html#trigger-audit">audit</a>"#,
+        r#"<a href="../relations/inferred.html">sampled</a>"#,
+        r##"<a href="#columns">columns</a>"##,
+    ));
+    assert!(site::relative_links(html, 0).contains(r#"href="table/customers.html""#));
+}
+
+#[test]
+fn names_cannot_leave_their_directory() {
+    assert_eq!(page::path_segment("order_items-2"), "order_items-2");
+    assert_eq!(page::path_segment("../etc"), "%2E%2E%2Fetc");
+    assert_eq!(page::path_segment("a?b#c d"), "a%3Fb%23c%20d");
+    assert_eq!(page::path_segment("顧客"), "%E9%A1%A7%E5%AE%A2");
+
+    let url = format!("/table/{}", page::path_segment("../etc"));
+    assert_eq!(site::file_path(&url), "table/%2E%2E%2Fetc.html");
+    assert_eq!(
+        site::relative_links(&format!(r#"<a href="{}">x</a>"#, url), 1),
+        r#"<a href="../table/%252E%252E%252Fetc.html">x</a>"#,
+    );
+}
+
+#[test]
+fn table_links_encode_the_name() {
+    let mut catalog = shop();
+    catalog.tables.push(table("odd/name #1"));
+    catalog.table_sizes.insert(String::from("odd/name #1"), size());
+    let tera = handler::templates().unwrap();
+    let pages: HashMap<String, String> = site::catalog_pages(&tera, "shop", &catalog, Features::default()).unwrap()
+        .into_iter()
+        .map(|Page { path, contents }| (path, contents))
+        .collect();
+
+    assert!(pages["tables.html"].contains(r#"href="table/odd%252Fname%2520%25231.html""#));
+}
+
+#[test]
+fn table_list_works_without_a_server() {
+    let pages = pages(Features::default());
+    let tables = &pages["tables.html"];
+
+    assert_eq!(&pages["index.html"], tables);
+    assert!(tables.contains(r#"href="table/orders.html""#));
+    assert!(tables.contains(r#"href="schema.sql""#));
+    assert!(!tables.contains(r#"="/"#));
+    assert!(tables.contains(r#"action="search.html""#));
+    assert!(!tables.contains("/row-counts/refresh"));
+    assert!(pages["diagram.html"].contains(r#"href="diagram.mmd""#));
+    assert!(pages["diagram.mmd"].contains("customers"));
+}
+
+#[test]
+fn search_index_lists_tables_and_columns() {
+    let pages = pages(Features::default());
+    let documents: Vec<serde_json::Value> = serde_json::from_str(&pages[site::SEARCH_INDEX_FILE]).unwrap();
+
+    assert_eq!(documents.len(), 5);
+    assert!(documents.iter().any(|d| d["column_name"] == "customer_id" && d["table_name"] == "orders"));
+}
+
+#[test]
+fn search_page_reads_the_index() {
+    let pages = pages(Features::default());
+    let search = &pages["search.html"];
+
+    assert!(search.contains(&format!(r#"fetch("{}")"#, site::SEARCH_INDEX_FILE)));
+    assert!(search.contains(r#"action="search.html""#));
+    assert!(!search.contains(r#"="/"#));
+}
+
+#[test]
+fn disabled_features_are_left_out() {
+    let pages = pages(Features { inferred_relations: false, ..Features::default() });
+
+    assert!(!pages.contains_key("relations/inferred.html"));
+    assert!(!pages["tables.html"].contains("relations/inferred"));
+}